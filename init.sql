@@ -3,3 +3,26 @@ CREATE TABLE IF NOT EXISTS accounts (
     username VARCHAR(255) NOT NULL UNIQUE,
     password VARCHAR(255) NOT NULL
 );
+
+CREATE TABLE IF NOT EXISTS servers (
+    id UUID PRIMARY KEY,
+    name VARCHAR(64) NOT NULL UNIQUE,
+    brand VARCHAR(32) NOT NULL,
+    version VARCHAR(64) NOT NULL,
+    build VARCHAR(64),
+    channel VARCHAR(16) NOT NULL DEFAULT 'default',
+    directory VARCHAR(255) NOT NULL,
+    java_major INTEGER,
+    restart_policy JSONB NOT NULL,
+    launch_profile JSONB NOT NULL,
+    launch_target JSONB
+);
+
+CREATE TABLE IF NOT EXISTS server_plugins (
+    id UUID PRIMARY KEY,
+    server_id UUID NOT NULL REFERENCES servers (id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    version VARCHAR(255) NOT NULL,
+    source VARCHAR(16) NOT NULL DEFAULT 'hangar',
+    UNIQUE (server_id, name)
+);
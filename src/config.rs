@@ -17,6 +17,8 @@ limitations under the License.
 
 use std::{error::Error, str::from_utf8};
 
+use crate::http::artifact_cache::CacheSettings;
+use crate::http::upstream::Upstreams;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -33,6 +35,10 @@ pub(crate) struct Config {
 	keyring_key: [u8; 32],
 	connection_string: String,
 	port: u16,
+	#[serde(default)]
+	upstreams: Upstreams,
+	#[serde(default)]
+	cache: CacheSettings,
 }
 
 impl Config {
@@ -41,6 +47,8 @@ impl Config {
 			keyring_key: [0u8; 32],
 			connection_string: "NaN".to_string(),
 			port: 5050,
+			upstreams: Upstreams::default(),
+			cache: CacheSettings::default(),
 		}
 	}
 
@@ -52,6 +60,14 @@ impl Config {
 		&self.connection_string
 	}
 
+	pub(crate) fn upstreams(&self) -> &Upstreams {
+		&self.upstreams
+	}
+
+	pub(crate) fn cache(&self) -> &CacheSettings {
+		&self.cache
+	}
+
 	pub(crate) fn with_key(&mut self, key: [u8; 32]) -> &mut Self {
 		self.keyring_key = key;
 		self
@@ -15,3 +15,5 @@
  */
 
 pub mod accounts;
+pub mod server_plugins;
+pub mod servers;
@@ -0,0 +1,48 @@
+/*
+ * Copyright 2025 seasnail1
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+use sea_orm::entity::prelude::*;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "server_plugins")]
+pub struct Model {
+	#[sea_orm(primary_key, auto_increment = false)]
+	pub id: Uuid,
+	pub server_id: Uuid,
+	pub name: String,
+	pub version: String,
+	pub source: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "super::servers::Entity",
+		from = "Column::ServerId",
+		to = "super::servers::Column::Id",
+		on_delete = "Cascade"
+	)]
+	Server,
+}
+
+impl Related<super::servers::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::Server.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,53 @@
+/*
+ * Copyright 2025 seasnail1
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+*/
+
+use sea_orm::entity::prelude::*;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "servers")]
+pub struct Model {
+	#[sea_orm(primary_key, auto_increment = false)]
+	pub id: Uuid,
+	#[sea_orm(unique)]
+	pub name: String,
+	pub brand: String,
+	pub version: String,
+	pub build: Option<String>,
+	pub channel: String,
+	pub directory: String,
+	pub java_major: Option<i32>,
+	#[sea_orm(column_type = "JsonBinary")]
+	pub restart_policy: Json,
+	#[sea_orm(column_type = "JsonBinary")]
+	pub launch_profile: Json,
+	#[sea_orm(column_type = "JsonBinary", nullable)]
+	pub launch_target: Option<Json>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(has_many = "super::server_plugins::Entity")]
+	Plugins,
+}
+
+impl Related<super::server_plugins::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::Plugins.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
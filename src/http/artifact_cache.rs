@@ -0,0 +1,484 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::download::{Checksum, DownloadProgress, DownloadTarget, download_verified};
+use crate::http::server_creator::HttpClientError;
+use log::{debug, info, warn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs::{copy, create_dir_all, read, remove_file, rename, write};
+use tokio::sync::{Mutex, watch};
+
+const INDEX_FILE: &str = "index.json";
+
+/// Where downloaded jars are kept and how much space they may take, set under `[cache]` in config.toml.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CacheSettings {
+	pub directory: PathBuf,
+	pub max_bytes: u64,
+}
+
+impl Default for CacheSettings {
+	fn default() -> Self {
+		Self {
+			directory: PathBuf::from("/app/cache"),
+			max_bytes: 4 << 30,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+struct CachedArtifact {
+	url: String,
+	size: u64,
+	last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheIndex {
+	loaded: bool,
+	entries: HashMap<String, CachedArtifact>,
+}
+
+/// Downloaded jars shared between servers, stored by checksum and evicted least recently used first.
+#[derive(Clone)]
+pub struct ArtifactCache {
+	root: PathBuf,
+	max_bytes: u64,
+	index: Arc<Mutex<CacheIndex>>,
+	// one lock per object so two servers asking for the same jar download it once.
+	in_flight: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+}
+
+impl ArtifactCache {
+	pub fn new(root: impl Into<PathBuf>, max_bytes: u64) -> Self {
+		Self {
+			root: root.into(),
+			max_bytes,
+			index: Arc::new(Mutex::new(CacheIndex::default())),
+			in_flight: Arc::new(Mutex::new(HashMap::new())),
+		}
+	}
+
+	pub fn with_settings(settings: &CacheSettings) -> Self {
+		Self::new(settings.directory.clone(), settings.max_bytes)
+	}
+
+	/// Puts `target` at `dest`, downloading it into the cache first if it isn't there yet.
+	/// `dest` is a private copy, so servers editing their jar can't change the cached one.
+	pub async fn fetch(
+		&self,
+		client: &Client,
+		target: &DownloadTarget,
+		dest: &Path,
+		progress: Option<&watch::Sender<DownloadProgress>>,
+	) -> Result<(), HttpClientError> {
+		let key: String = object_key(target)?;
+		let object: PathBuf = self.object_path(&key);
+
+		let key_lock = self.key_lock(&key).await;
+		let _guard = key_lock.lock().await;
+
+		if self.touch(&key, &target.url).await? {
+			debug!("Cache hit for {}", target.url);
+		} else {
+			create_dir_all(self.root.join("objects")).await?;
+			download_verified(client, target, &object, progress).await?;
+
+			let size: u64 = tokio::fs::metadata(&object).await?.len();
+			self.record(&key, &target.url, size).await?;
+		}
+
+		copy_out(&object, dest).await?;
+		Ok(())
+	}
+
+	#[cfg(test)]
+	pub async fn size(&self) -> Result<u64, HttpClientError> {
+		let mut index = self.index.lock().await;
+		self.ensure_loaded(&mut index).await?;
+
+		Ok(index.entries.values().map(|entry| entry.size).sum())
+	}
+
+	fn object_path(&self, key: &str) -> PathBuf {
+		self.root.join("objects").join(key)
+	}
+
+	async fn key_lock(&self, key: &str) -> Arc<Mutex<()>> {
+		let mut in_flight = self.in_flight.lock().await;
+
+		// nobody else holds a lock that's only referenced by the map.
+		in_flight.retain(|_, lock| Arc::strong_count(lock) > 1);
+
+		in_flight.entry(key.to_string()).or_default().clone()
+	}
+
+	/// Marks `key` as used and returns whether the cached file is still there.
+	async fn touch(&self, key: &str, url: &str) -> Result<bool, HttpClientError> {
+		let mut index = self.index.lock().await;
+		self.ensure_loaded(&mut index).await?;
+
+		if !index.entries.contains_key(key) {
+			return Ok(false);
+		}
+
+		if !self.object_path(key).exists() {
+			warn!("Cached copy of {} went missing, downloading again", url);
+			index.entries.remove(key);
+			self.save(&index).await?;
+			return Ok(false);
+		}
+
+		if let Some(entry) = index.entries.get_mut(key) {
+			entry.last_used = now();
+		}
+
+		self.save(&index).await?;
+		Ok(true)
+	}
+
+	async fn record(&self, key: &str, url: &str, size: u64) -> Result<(), HttpClientError> {
+		let mut index = self.index.lock().await;
+		self.ensure_loaded(&mut index).await?;
+
+		index.entries.insert(
+			key.to_string(),
+			CachedArtifact {
+				url: url.to_string(),
+				size,
+				last_used: now(),
+			},
+		);
+
+		self.evict(&mut index, key).await;
+		self.save(&index).await
+	}
+
+	// drops the least recently used objects until the cache fits again, never the one just added
+	// nor one that another fetch is still copying out.
+	async fn evict(&self, index: &mut CacheIndex, keep: &str) {
+		let mut total: u64 = index.entries.values().map(|entry| entry.size).sum();
+
+		// held until eviction ends so no fetch can start on a key being removed.
+		let in_flight = self.in_flight.lock().await;
+		let busy = |key: &str| {
+			in_flight
+				.get(key)
+				.is_some_and(|lock| Arc::strong_count(lock) > 1)
+		};
+
+		let mut candidates: Vec<(String, u64, u64)> = index
+			.entries
+			.iter()
+			.filter(|(key, _)| key.as_str() != keep && !busy(key))
+			.map(|(key, entry)| (key.clone(), entry.last_used, entry.size))
+			.collect();
+		candidates.sort_by_key(|(_, last_used, _)| *last_used);
+
+		for (key, _, size) in candidates {
+			if total <= self.max_bytes {
+				break;
+			}
+
+			match remove_file(self.object_path(&key)).await {
+				Ok(()) => {}
+				Err(e) if e.kind() == ErrorKind::NotFound => {}
+				Err(e) => {
+					warn!("Could not evict {} from the cache: {}", key, e);
+					continue;
+				}
+			}
+
+			info!("Evicted {} from the artifact cache", key);
+			index.entries.remove(&key);
+			total -= size;
+		}
+	}
+
+	async fn ensure_loaded(&self, index: &mut CacheIndex) -> Result<(), HttpClientError> {
+		if index.loaded {
+			return Ok(());
+		}
+
+		index.entries = match read(self.root.join(INDEX_FILE)).await {
+			Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+				warn!("Artifact cache index is corrupt, starting over: {}", e);
+				HashMap::new()
+			}),
+			Err(e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+			Err(e) => return Err(e.into()),
+		};
+		index.loaded = true;
+
+		Ok(())
+	}
+
+	async fn save(&self, index: &CacheIndex) -> Result<(), HttpClientError> {
+		create_dir_all(&self.root).await?;
+
+		let path: PathBuf = self.root.join(INDEX_FILE);
+		let tmp: PathBuf = self.root.join(format!("{}.tmp", INDEX_FILE));
+
+		let json = serde_json::to_vec_pretty(&index.entries)
+			.map_err(|e| HttpClientError::FileError(std::io::Error::other(e)))?;
+
+		write(&tmp, json).await?;
+		rename(&tmp, &path).await?;
+		Ok(())
+	}
+}
+
+/// Objects with a published checksum are stored under it, anything else under a hash of its URL.
+/// The checksum ends up in a path, so anything but plain hex is refused.
+fn object_key(target: &DownloadTarget) -> Result<String, HttpClientError> {
+	let (algorithm, hash) = match &target.checksum {
+		Some(checksum) if !checksum.is_well_formed() => {
+			return Err(HttpClientError::InvalidManifest("malformed checksum"));
+		}
+		Some(Checksum::Md5(hash)) => ("md5", hash),
+		Some(Checksum::Sha1(hash)) => ("sha1", hash),
+		Some(Checksum::Sha256(hash)) => ("sha256", hash),
+		None => {
+			return Ok(format!(
+				"url-{}",
+				hex::encode(Sha256::digest(target.url.as_bytes()))
+			));
+		}
+	};
+
+	Ok(format!("{}-{}", algorithm, hash.to_ascii_lowercase()))
+}
+
+async fn copy_out(object: &Path, dest: &Path) -> Result<(), HttpClientError> {
+	match remove_file(dest).await {
+		Ok(()) => {}
+		Err(e) if e.kind() == ErrorKind::NotFound => {}
+		Err(e) => return Err(e.into()),
+	}
+
+	copy(object, dest).await?;
+	Ok(())
+}
+
+// nanoseconds so jars fetched back to back still have an order.
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|elapsed| elapsed.as_nanos() as u64)
+		.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::extract::Path as UrlPath;
+	use axum::routing::get;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use tokio::net::TcpListener;
+
+	/// Serves `<name>` as the body of `/<name>`, counting requests.
+	async fn serve(requests: Arc<AtomicUsize>) -> String {
+		let app = Router::new().route(
+			"/{name}",
+			get(move |UrlPath(name): UrlPath<String>| {
+				let requests = requests.clone();
+
+				async move {
+					requests.fetch_add(1, Ordering::Relaxed);
+					name
+				}
+			}),
+		);
+
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+		format!("http://{}", addr)
+	}
+
+	fn temp_root(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("lunara-cache-{}-{}", name, std::process::id()))
+	}
+
+	#[tokio::test]
+	async fn second_fetch_is_served_from_cache() {
+		let root = temp_root("hit");
+		let requests = Arc::new(AtomicUsize::new(0));
+		let base = serve(requests.clone()).await;
+		let cache = ArtifactCache::new(&root, CacheSettings::default().max_bytes);
+
+		let target = DownloadTarget {
+			url: format!("{}/paper.jar", base),
+			checksum: Some(Checksum::Sha1(hex::encode(sha1::Sha1::digest(
+				b"paper.jar",
+			)))),
+			build: None,
+		};
+
+		let client = Client::new();
+		for dest in ["one.jar", "two.jar"] {
+			cache
+				.fetch(&client, &target, &root.join(dest), None)
+				.await
+				.unwrap();
+		}
+
+		assert_eq!(requests.load(Ordering::Relaxed), 1);
+		assert_eq!(std::fs::read(root.join("two.jar")).unwrap(), b"paper.jar");
+
+		// a server changing its jar leaves the cached copy alone.
+		std::fs::write(root.join("one.jar"), b"patched").unwrap();
+		cache
+			.fetch(&client, &target, &root.join("three.jar"), None)
+			.await
+			.unwrap();
+		assert_eq!(std::fs::read(root.join("three.jar")).unwrap(), b"paper.jar");
+
+		// a fresh cache over the same directory picks up the index.
+		let reopened = ArtifactCache::new(&root, CacheSettings::default().max_bytes);
+		assert_eq!(reopened.size().await.unwrap(), 9);
+
+		std::fs::remove_dir_all(root).unwrap();
+	}
+
+	#[tokio::test]
+	async fn evicts_least_recently_used() {
+		let root = temp_root("evict");
+		let requests = Arc::new(AtomicUsize::new(0));
+		let base = serve(requests.clone()).await;
+		let cache = ArtifactCache::new(&root, 20);
+		let client = Client::new();
+
+		let target = |name: &str| DownloadTarget {
+			url: format!("{}/{}", base, name),
+			checksum: None,
+			build: None,
+		};
+
+		for name in ["first.jar", "second.jar", "first.jar", "third.jar"] {
+			cache
+				.fetch(&client, &target(name), &root.join(name), None)
+				.await
+				.unwrap();
+		}
+
+		// first.jar was used after second.jar, so second.jar is the one that had to go.
+		assert_eq!(requests.load(Ordering::Relaxed), 3);
+		assert_eq!(cache.size().await.unwrap(), 18);
+		assert!(
+			!cache
+				.object_path(&object_key(&target("second.jar")).unwrap())
+				.exists()
+		);
+		assert!(
+			cache
+				.object_path(&object_key(&target("first.jar")).unwrap())
+				.exists()
+		);
+
+		std::fs::remove_dir_all(root).unwrap();
+	}
+
+	#[tokio::test]
+	async fn does_not_evict_objects_in_use() {
+		let root = temp_root("in-use");
+		let requests = Arc::new(AtomicUsize::new(0));
+		let base = serve(requests.clone()).await;
+		let cache = ArtifactCache::new(&root, 20);
+		let client = Client::new();
+
+		let target = |name: &str| DownloadTarget {
+			url: format!("{}/{}", base, name),
+			checksum: None,
+			build: None,
+		};
+
+		for name in ["first.jar", "second.jar"] {
+			cache
+				.fetch(&client, &target(name), &root.join(name), None)
+				.await
+				.unwrap();
+		}
+
+		// another fetch is still busy with first.jar, the least recently used one.
+		let in_use = cache
+			.key_lock(&object_key(&target("first.jar")).unwrap())
+			.await;
+		cache
+			.fetch(&client, &target("third.jar"), &root.join("third.jar"), None)
+			.await
+			.unwrap();
+
+		assert!(
+			cache
+				.object_path(&object_key(&target("first.jar")).unwrap())
+				.exists()
+		);
+		assert!(
+			!cache
+				.object_path(&object_key(&target("second.jar")).unwrap())
+				.exists()
+		);
+
+		drop(in_use);
+		std::fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn objects_are_keyed_by_checksum_or_url() {
+		let target = |checksum: Option<Checksum>| DownloadTarget {
+			url: String::from("https://example.com/a.jar"),
+			checksum,
+			build: None,
+		};
+
+		let sha256 = format!("ABCD{}", "0".repeat(60));
+
+		assert_eq!(
+			object_key(&target(Some(Checksum::Sha256(sha256)))).unwrap(),
+			format!("sha256-abcd{}", "0".repeat(60))
+		);
+		assert!(object_key(&target(None)).unwrap().starts_with("url-"));
+	}
+
+	#[test]
+	fn malformed_checksums_never_become_paths() {
+		for checksum in [
+			Checksum::Sha1(format!("../../{}", "a".repeat(34))),
+			Checksum::Sha256(format!("{}/{}", "a".repeat(31), "b".repeat(32))),
+			Checksum::Md5(String::from("abcd")),
+			Checksum::Md5("z".repeat(32)),
+		] {
+			let target = DownloadTarget {
+				url: String::from("https://example.com/a.jar"),
+				checksum: Some(checksum),
+				build: None,
+			};
+
+			assert!(object_key(&target).is_err());
+		}
+	}
+}
@@ -0,0 +1,567 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::server_creator::HttpClientError;
+use crate::http::upstream::Upstreams;
+use log::{info, warn};
+use md5::Md5;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, Response, StatusCode};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions, copy, metadata, remove_file, rename};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::watch;
+
+const MAX_ATTEMPTS: u32 = 3;
+const READ_BUFFER: usize = 64 * 1024;
+
+/// Expected digest of a download, hex encoded as the upstream APIs publish it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+	Md5(String),
+	Sha1(String),
+	Sha256(String),
+}
+
+impl Checksum {
+	#[cfg(test)]
+	pub fn verify(&self, bytes: &[u8]) -> Result<(), HttpClientError> {
+		let mut hasher = Hasher::new(self);
+		hasher.update(bytes);
+
+		self.check(hasher.finish())
+	}
+
+	/// Whether the hash is plain hex of the length the algorithm produces.
+	/// Upstream metadata isn't trusted to hold one.
+	pub fn is_well_formed(&self) -> bool {
+		let (hash, len) = match self {
+			Checksum::Md5(hash) => (hash, 32),
+			Checksum::Sha1(hash) => (hash, 40),
+			Checksum::Sha256(hash) => (hash, 64),
+		};
+
+		hash.len() == len && hash.chars().all(|c| c.is_ascii_hexdigit())
+	}
+
+	fn check(&self, actual: String) -> Result<(), HttpClientError> {
+		let expected = match self {
+			Checksum::Md5(expected) | Checksum::Sha1(expected) | Checksum::Sha256(expected) => {
+				expected
+			}
+		};
+
+		if !expected.eq_ignore_ascii_case(&actual) {
+			return Err(HttpClientError::ChecksumMismatch {
+				expected: expected.clone(),
+				actual,
+			});
+		}
+
+		Ok(())
+	}
+}
+
+enum Hasher {
+	Md5(Md5),
+	Sha1(Sha1),
+	Sha256(Sha256),
+}
+
+impl Hasher {
+	fn new(checksum: &Checksum) -> Self {
+		match checksum {
+			Checksum::Md5(_) => Hasher::Md5(Md5::new()),
+			Checksum::Sha1(_) => Hasher::Sha1(Sha1::new()),
+			Checksum::Sha256(_) => Hasher::Sha256(Sha256::new()),
+		}
+	}
+
+	fn update(&mut self, bytes: &[u8]) {
+		match self {
+			Hasher::Md5(hasher) => hasher.update(bytes),
+			Hasher::Sha1(hasher) => hasher.update(bytes),
+			Hasher::Sha256(hasher) => hasher.update(bytes),
+		}
+	}
+
+	fn finish(self) -> String {
+		match self {
+			Hasher::Md5(hasher) => hex::encode(hasher.finalize()),
+			Hasher::Sha1(hasher) => hex::encode(hasher.finalize()),
+			Hasher::Sha256(hasher) => hex::encode(hasher.finalize()),
+		}
+	}
+}
+
+/// Where to fetch a file from and what it should hash to.
+#[derive(Debug, Clone)]
+pub struct DownloadTarget {
+	pub url: String,
+	pub checksum: Option<Checksum>,
+	/// Upstream build the url was resolved from, when the brand has builds.
+	pub build: Option<String>,
+}
+
+impl DownloadTarget {
+	pub fn file_name(&self) -> &str {
+		self.url.split('/').next_back().unwrap_or("server.jar")
+	}
+}
+
+/// Bytes written so far, and the full size when the server sent one.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct DownloadProgress {
+	pub downloaded: u64,
+	pub total: Option<u64>,
+}
+
+/// Streams `target` to `dest`, rejecting error responses and bad checksums.
+/// Data goes to `<dest>.part` first. A dropped connection is retried from where it stopped
+/// with a Range request, and a `.part` left over from an earlier call is resumed the same way.
+/// Only targets with a checksum are resumed, nothing else would catch a stale `.part`.
+/// `dest` only appears once the checksum matched.
+pub async fn download_verified(
+	client: &Client,
+	target: &DownloadTarget,
+	dest: &Path,
+	progress: Option<&watch::Sender<DownloadProgress>>,
+) -> Result<(), HttpClientError> {
+	let part: PathBuf = part_path(dest);
+
+	if let Some(mirrored) = Upstreams::current().mirror_file(&target.url).await? {
+		copy(&mirrored, &part).await?;
+		return finish(target, &part, dest).await;
+	}
+
+	let mut attempt: u32 = 1;
+
+	loop {
+		match fetch_to_part(client, target, &part, progress).await {
+			Ok(()) => break,
+			Err(HttpClientError::ManifestFetch(error)) if attempt < MAX_ATTEMPTS => {
+				warn!(
+					"Download of {} interrupted ({}), resuming",
+					target.url, error
+				);
+				attempt += 1;
+			}
+			Err(error) => return Err(error),
+		}
+	}
+
+	finish(target, &part, dest).await
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+	let mut part = dest.to_path_buf().into_os_string();
+	part.push(".part");
+	PathBuf::from(part)
+}
+
+async fn fetch_to_part(
+	client: &Client,
+	target: &DownloadTarget,
+	part: &Path,
+	progress: Option<&watch::Sender<DownloadProgress>>,
+) -> Result<(), HttpClientError> {
+	let mut offset: u64 = match target.checksum {
+		Some(_) => metadata(part).await.map(|m| m.len()).unwrap_or(0),
+		None => 0,
+	};
+
+	let mut response: Response = loop {
+		let mut request = client.get(&target.url);
+
+		if offset > 0 {
+			request = request.header(RANGE, format!("bytes={}-", offset));
+		}
+
+		let response: Response = request.send().await?;
+
+		// a range starting anywhere else would be appended in the wrong place.
+		if response.status() == StatusCode::PARTIAL_CONTENT
+			&& offset > 0
+			&& content_range_start(&response) != Some(offset)
+		{
+			warn!(
+				"{} answered a resume with the wrong range, starting over",
+				target.url
+			);
+			offset = 0;
+			continue;
+		}
+
+		break response;
+	};
+
+	let (mut file, mut downloaded) = match response.status() {
+		StatusCode::PARTIAL_CONTENT if offset > 0 => {
+			(OpenOptions::new().append(true).open(part).await?, offset)
+		}
+		// the part file already holds everything, the checksum decides if it's any good.
+		StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return Ok(()),
+		status if status.is_success() => (File::create(part).await?, 0),
+		status => {
+			return Err(HttpClientError::UnexpectedStatus(
+				status.as_u16(),
+				target.url.clone(),
+			));
+		}
+	};
+
+	let total: Option<u64> = response.content_length().map(|len| len + downloaded);
+	report(progress, downloaded, total);
+
+	while let Some(chunk) = response.chunk().await? {
+		file.write_all(&chunk).await?;
+		downloaded += chunk.len() as u64;
+
+		report(progress, downloaded, total);
+	}
+
+	file.flush().await?;
+	Ok(())
+}
+
+/// First byte of a `Content-Range: bytes <start>-<end>/<size>` header.
+fn content_range_start(response: &Response) -> Option<u64> {
+	response
+		.headers()
+		.get(CONTENT_RANGE)?
+		.to_str()
+		.ok()?
+		.strip_prefix("bytes ")?
+		.split('-')
+		.next()?
+		.trim()
+		.parse()
+		.ok()
+}
+
+fn report(progress: Option<&watch::Sender<DownloadProgress>>, downloaded: u64, total: Option<u64>) {
+	if let Some(progress) = progress {
+		progress.send_replace(DownloadProgress { downloaded, total });
+	}
+}
+
+async fn finish(target: &DownloadTarget, part: &Path, dest: &Path) -> Result<(), HttpClientError> {
+	if let Some(checksum) = &target.checksum {
+		if let Err(error) = verify_file(checksum, part).await {
+			if let Err(remove_error) = remove_file(part).await {
+				warn!(
+					"Could not remove bad download {}: {}",
+					part.display(),
+					remove_error
+				);
+			}
+
+			return Err(error);
+		}
+	} else {
+		warn!(
+			"No checksum published for {}, skipping verification",
+			target.url
+		);
+	}
+
+	rename(part, dest).await?;
+
+	info!("Downloaded {} to {}", target.url, dest.display());
+	Ok(())
+}
+
+async fn verify_file(checksum: &Checksum, path: &Path) -> Result<(), HttpClientError> {
+	let mut file = File::open(path).await?;
+	let mut hasher = Hasher::new(checksum);
+	let mut buffer = vec![0u8; READ_BUFFER];
+
+	loop {
+		let read = file.read(&mut buffer).await?;
+
+		if read == 0 {
+			break;
+		}
+
+		hasher.update(&buffer[..read]);
+	}
+
+	checksum.check(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::http::HeaderMap;
+	use axum::response::IntoResponse;
+	use axum::routing::get;
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use tokio::net::TcpListener;
+
+	#[test]
+	fn verifies_sha1_and_sha256() {
+		let sha1 = Checksum::Sha1(String::from("a9993e364706816aba3e25717850c26c9cd0d89d"));
+		let sha256 = Checksum::Sha256(String::from(
+			"BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD",
+		));
+
+		assert!(sha1.verify(b"abc").is_ok());
+		assert!(sha256.verify(b"abc").is_ok());
+	}
+
+	#[test]
+	fn verifies_md5() {
+		let md5 = Checksum::Md5(String::from("900150983cd24fb0d6963f7d28e17f72"));
+
+		assert!(md5.verify(b"abc").is_ok());
+		assert!(md5.verify(b"abd").is_err());
+	}
+
+	#[test]
+	fn mismatch_reports_both_digests() {
+		let checksum = Checksum::Sha1(String::from("a9993e364706816aba3e25717850c26c9cd0d89d"));
+
+		match checksum.verify(b"<html>Not Found</html>") {
+			Err(HttpClientError::ChecksumMismatch { expected, actual }) => {
+				assert_eq!(expected, "a9993e364706816aba3e25717850c26c9cd0d89d");
+				assert_ne!(actual, expected);
+			}
+			_ => panic!("expected a checksum mismatch"),
+		}
+	}
+
+	fn temp_dest(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("lunara-{}-{}.jar", name, std::process::id()))
+	}
+
+	#[tokio::test]
+	async fn bad_checksum_removes_file() {
+		let dest = temp_dest("bad-checksum");
+		let target = DownloadTarget {
+			url: String::from("https://example.com/server.jar"),
+			checksum: Some(Checksum::Sha256(String::from("00"))),
+			build: None,
+		};
+
+		tokio::fs::write(part_path(&dest), b"not a jar")
+			.await
+			.unwrap();
+
+		let result = finish(&target, &part_path(&dest), &dest).await;
+
+		assert!(matches!(
+			result,
+			Err(HttpClientError::ChecksumMismatch { .. })
+		));
+		assert!(!dest.exists());
+		assert!(!part_path(&dest).exists());
+	}
+
+	fn range_start(headers: &HeaderMap) -> usize {
+		headers
+			.get("range")
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.strip_prefix("bytes="))
+			.and_then(|value| value.trim_end_matches('-').parse().ok())
+			.unwrap_or(0)
+	}
+
+	/// Serves `BODY`, honouring `Range: bytes=<n>-` and counting ranged requests.
+	/// `/misaligned.jar` answers every range with the whole body labelled as such.
+	async fn serve_body(ranged: Arc<AtomicUsize>) -> String {
+		let misaligned = ranged.clone();
+
+		let app = Router::new()
+			.route(
+				"/server.jar",
+				get(move |headers: HeaderMap| {
+					let ranged = ranged.clone();
+
+					async move {
+						let start: usize = range_start(&headers);
+
+						if start == 0 {
+							return (axum::http::StatusCode::OK, BODY[..].to_vec()).into_response();
+						}
+
+						ranged.fetch_add(1, Ordering::Relaxed);
+						(
+							axum::http::StatusCode::PARTIAL_CONTENT,
+							[(
+								"content-range",
+								format!("bytes {}-{}/{}", start, BODY.len() - 1, BODY.len()),
+							)],
+							BODY[start..].to_vec(),
+						)
+							.into_response()
+					}
+				}),
+			)
+			.route(
+				"/misaligned.jar",
+				get(move |headers: HeaderMap| {
+					let ranged = misaligned.clone();
+
+					async move {
+						if range_start(&headers) == 0 {
+							return (axum::http::StatusCode::OK, BODY[..].to_vec()).into_response();
+						}
+
+						ranged.fetch_add(1, Ordering::Relaxed);
+						(
+							axum::http::StatusCode::PARTIAL_CONTENT,
+							[(
+								"content-range",
+								format!("bytes 0-{}/{}", BODY.len() - 1, BODY.len()),
+							)],
+							BODY[..].to_vec(),
+						)
+							.into_response()
+					}
+				}),
+			);
+
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+
+		tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+		format!("http://{}/server.jar", addr)
+	}
+
+	const BODY: &[u8; 26] = b"abcdefghijklmnopqrstuvwxyz";
+
+	#[tokio::test]
+	async fn resumes_partial_download_and_reports_progress() {
+		let ranged = Arc::new(AtomicUsize::new(0));
+		let url = serve_body(ranged.clone()).await;
+		let dest = temp_dest("resume");
+		let target = DownloadTarget {
+			url,
+			checksum: Some(Checksum::Sha1(hex::encode(Sha1::digest(BODY)))),
+			build: None,
+		};
+
+		tokio::fs::write(part_path(&dest), &BODY[..10])
+			.await
+			.unwrap();
+
+		let (progress_tx, progress_rx) = watch::channel(DownloadProgress::default());
+
+		download_verified(&Client::new(), &target, &dest, Some(&progress_tx))
+			.await
+			.unwrap();
+
+		assert_eq!(tokio::fs::read(&dest).await.unwrap(), BODY);
+		assert_eq!(ranged.load(Ordering::Relaxed), 1);
+		assert_eq!(
+			*progress_rx.borrow(),
+			DownloadProgress {
+				downloaded: 26,
+				total: Some(26),
+			}
+		);
+
+		remove_file(&dest).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn misaligned_resume_starts_over() {
+		let ranged = Arc::new(AtomicUsize::new(0));
+		let url = serve_body(ranged.clone()).await;
+		let dest = temp_dest("misaligned");
+		let target = DownloadTarget {
+			url: url.replace("server.jar", "misaligned.jar"),
+			checksum: Some(Checksum::Sha1(hex::encode(Sha1::digest(BODY)))),
+			build: None,
+		};
+
+		tokio::fs::write(part_path(&dest), &BODY[..10])
+			.await
+			.unwrap();
+
+		download_verified(&Client::new(), &target, &dest, None)
+			.await
+			.unwrap();
+
+		assert_eq!(tokio::fs::read(&dest).await.unwrap(), BODY);
+		assert_eq!(ranged.load(Ordering::Relaxed), 1);
+
+		remove_file(&dest).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn part_without_checksum_is_not_resumed() {
+		let ranged = Arc::new(AtomicUsize::new(0));
+		let url = serve_body(ranged.clone()).await;
+		let dest = temp_dest("unverified");
+		let target = DownloadTarget {
+			url,
+			checksum: None,
+			build: None,
+		};
+
+		tokio::fs::write(part_path(&dest), b"stale leftover")
+			.await
+			.unwrap();
+
+		download_verified(&Client::new(), &target, &dest, None)
+			.await
+			.unwrap();
+
+		assert_eq!(tokio::fs::read(&dest).await.unwrap(), BODY);
+		assert_eq!(ranged.load(Ordering::Relaxed), 0);
+
+		remove_file(&dest).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn error_status_is_rejected() {
+		let url = serve_body(Arc::new(AtomicUsize::new(0))).await;
+		let dest = temp_dest("missing");
+		let target = DownloadTarget {
+			url: url.replace("server.jar", "missing.jar"),
+			checksum: None,
+			build: None,
+		};
+
+		let result = download_verified(&Client::new(), &target, &dest, None).await;
+
+		assert!(matches!(
+			result,
+			Err(HttpClientError::UnexpectedStatus(404, _))
+		));
+		assert!(!dest.exists());
+	}
+
+	#[test]
+	fn file_name_comes_from_url() {
+		let target = DownloadTarget {
+			url: String::from(
+				"https://api.papermc.io/v2/projects/paper/versions/1.21.1/builds/1/downloads/paper-1.21.1-1.jar",
+			),
+			checksum: None,
+			build: None,
+		};
+
+		assert_eq!(target.file_name(), "paper-1.21.1-1.jar");
+	}
+}
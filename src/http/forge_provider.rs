@@ -0,0 +1,565 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::download::{Checksum, DownloadTarget};
+use crate::http::server_creator::HttpClientError;
+use crate::http::server_provider::{CatalogBuild, CatalogVersion, Mojang, ServerProvider};
+use crate::http::upstream::Upstreams;
+use crate::mc::java_runtime::{JavaRegistry, fallback_major};
+use crate::mc::launch_profile::LaunchTarget;
+use crate::mc::server::{BuildInfo, ReleaseChannel};
+use log::{info, warn};
+use reqwest::Client;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::path::Path;
+use std::process::Output;
+use std::time::Duration;
+use tokio::fs::{read_dir, read_to_string, remove_file};
+use tokio::process::Command;
+use tokio::time::timeout;
+
+// how much installer output ends up in the error.
+const OUTPUT_TAIL_LINES: usize = 20;
+// installers download their libraries, give a slow mirror time but not forever.
+const INSTALLER_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Forge installers, builds are Forge versions like `47.3.0`.
+pub(crate) struct Forge;
+
+impl Forge {
+	async fn promotions(&self, client: &Client) -> Result<Value, HttpClientError> {
+		let upstreams: &Upstreams = Upstreams::current();
+		let resp: Value = upstreams.json(client, &upstreams.forge_promotions).await?;
+
+		Ok(resp["promos"].clone())
+	}
+}
+
+impl ServerProvider for Forge {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		let promos: Value = self.promotions(client).await?;
+
+		let mut versions: Vec<String> = promos
+			.as_object()
+			.into_iter()
+			.flat_map(|promos| promos.keys())
+			.filter_map(|key| {
+				key.strip_suffix("-latest")
+					.or_else(|| key.strip_suffix("-recommended"))
+			})
+			.map(String::from)
+			.collect();
+
+		sort_newest_first(&mut versions);
+		versions.dedup();
+
+		Ok(versions.into_iter().map(CatalogVersion::from_id).collect())
+	}
+
+	async fn builds(
+		&self,
+		client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError> {
+		let prefix: String = format!("{}-", version);
+
+		let mut builds: Vec<String> = maven_versions(client, &Upstreams::current().forge_maven)
+			.await?
+			.iter()
+			.filter_map(|full| full.strip_prefix(&prefix))
+			.map(String::from)
+			.collect();
+
+		if builds.is_empty() {
+			return Err(HttpClientError::VersionNotFound(version.to_string()));
+		}
+
+		sort_newest_first(&mut builds);
+		Ok(builds.into_iter().map(CatalogBuild::from_id).collect())
+	}
+
+	/// Without a build this takes the recommended one, the experimental channel takes the latest.
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError> {
+		let version: &str = &info.version;
+		let build: String = match info.build.as_deref() {
+			Some(build) => build.to_string(),
+			None => {
+				let promos: Value = self.promotions(client).await?;
+
+				promoted_build(&promos, version, &info.channel).ok_or_else(|| {
+					HttpClientError::VersionNotFound(format!(
+						"{} has no {} builds",
+						version, info.channel
+					))
+				})?
+			}
+		};
+
+		let full: String = format!("{}-{}", version, build);
+		let url: String = format!(
+			"{}/{}/forge-{}-installer.jar",
+			Upstreams::current().forge_maven,
+			full,
+			full
+		);
+
+		maven_target(client, url, build).await
+	}
+
+	async fn post_install(
+		&self,
+		client: &Client,
+		version: &str,
+		dir: &Path,
+		jar: &str,
+	) -> Result<LaunchTarget, HttpClientError> {
+		run_installer(client, version, dir, jar).await
+	}
+
+	fn plugin_loaders(&self) -> &'static [&'static str] {
+		&["forge"]
+	}
+
+	fn plugin_dir(&self) -> &'static str {
+		"mods"
+	}
+}
+
+/// NeoForge installers, builds are NeoForge versions like `21.1.72` for Minecraft 1.21.1.
+pub(crate) struct NeoForge;
+
+impl ServerProvider for NeoForge {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		let mut versions: Vec<String> =
+			maven_versions(client, &Upstreams::current().neoforge_maven)
+				.await?
+				.iter()
+				.filter_map(|build| neoforge_minecraft_version(build))
+				.collect();
+
+		sort_newest_first(&mut versions);
+		versions.dedup();
+
+		Ok(versions.into_iter().map(CatalogVersion::from_id).collect())
+	}
+
+	async fn builds(
+		&self,
+		client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError> {
+		let prefix: String = neoforge_prefix(version)
+			.ok_or_else(|| HttpClientError::VersionNotFound(version.to_string()))?;
+
+		let mut builds: Vec<String> = maven_versions(client, &Upstreams::current().neoforge_maven)
+			.await?
+			.into_iter()
+			.filter(|build| build.starts_with(&prefix))
+			.collect();
+
+		if builds.is_empty() {
+			return Err(HttpClientError::VersionNotFound(version.to_string()));
+		}
+
+		sort_newest_first(&mut builds);
+
+		Ok(builds
+			.into_iter()
+			.map(|id| CatalogBuild {
+				channel: id.contains("-beta").then(|| String::from("beta")),
+				id,
+				released: None,
+			})
+			.collect())
+	}
+
+	/// Without a build this takes the newest stable one, the experimental channel also takes betas.
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError> {
+		let version: &str = &info.version;
+		let build: String = match info.build.as_deref() {
+			Some(build) => build.to_string(),
+			None => self
+				.builds(client, version)
+				.await?
+				.into_iter()
+				.find(|build| info.channel.accepts(build.channel.as_deref()))
+				.map(|build| build.id)
+				.ok_or_else(|| {
+					HttpClientError::VersionNotFound(format!(
+						"{} has no {} builds",
+						version, info.channel
+					))
+				})?,
+		};
+
+		let url: String = format!(
+			"{}/{}/neoforge-{}-installer.jar",
+			Upstreams::current().neoforge_maven,
+			build,
+			build
+		);
+
+		maven_target(client, url, build).await
+	}
+
+	async fn post_install(
+		&self,
+		client: &Client,
+		version: &str,
+		dir: &Path,
+		jar: &str,
+	) -> Result<LaunchTarget, HttpClientError> {
+		run_installer(client, version, dir, jar).await
+	}
+
+	fn plugin_loaders(&self) -> &'static [&'static str] {
+		&["neoforge"]
+	}
+
+	fn plugin_dir(&self) -> &'static str {
+		"mods"
+	}
+}
+
+/// Runs `java -jar <installer> --installServer` in `dir`, drops the installer and
+/// works out what to launch from what it left behind.
+async fn run_installer(
+	client: &Client,
+	version: &str,
+	dir: &Path,
+	installer: &str,
+) -> Result<LaunchTarget, HttpClientError> {
+	// the installer runs the same processors as the server, so it wants the same java.
+	let major: u32 = match Mojang.java_major(client, version).await {
+		Ok(major) => major,
+		Err(error) => {
+			warn!("Could not look up the required Java version: {}", error);
+			fallback_major(version)
+		}
+	};
+
+	let java: String = JavaRegistry::discover().await.executable_for(major);
+
+	info!("Running {} in {}", installer, dir.display());
+
+	let mut command = Command::new(java);
+	command
+		.args(["-jar", installer, "--installServer"])
+		.current_dir(dir);
+
+	let output: Output = output_within(command, installer, INSTALLER_TIMEOUT).await?;
+
+	if !output.status.success() {
+		return Err(HttpClientError::InstallerFailed(format!(
+			"{} exited with {}:\n{}",
+			installer,
+			output.status,
+			output_tail(&output)
+		)));
+	}
+
+	if let Err(error) = remove_file(dir.join(installer)).await {
+		warn!("Could not remove {}: {}", installer, error);
+	}
+
+	launch_target(dir).await
+}
+
+/// Modern installers write a `run.sh` pointing at an args file, older ones a universal jar.
+async fn launch_target(dir: &Path) -> Result<LaunchTarget, HttpClientError> {
+	if let Ok(script) = read_to_string(dir.join("run.sh")).await
+		&& let Some(args_file) = args_file_from_script(&script)
+	{
+		return Ok(LaunchTarget::ArgsFile(args_file));
+	}
+
+	let mut jars: Vec<String> = vec![];
+	let mut entries = read_dir(dir).await?;
+
+	while let Some(entry) = entries.next_entry().await? {
+		jars.push(entry.file_name().to_string_lossy().into_owned());
+	}
+
+	pick_server_jar(&jars)
+		.map(LaunchTarget::Jar)
+		.ok_or_else(|| {
+			HttpClientError::InstallerFailed(String::from(
+				"the installer finished but left nothing to launch",
+			))
+		})
+}
+
+fn args_file_from_script(script: &str) -> Option<String> {
+	script
+		.split_whitespace()
+		.filter_map(|token| token.strip_prefix('@'))
+		.find(|file| file.starts_with("libraries/") && file.ends_with("unix_args.txt"))
+		.map(String::from)
+}
+
+/// Forge promotes a recommended build once a version is stable, until then there's only the latest.
+fn promoted_build(promos: &Value, version: &str, channel: &ReleaseChannel) -> Option<String> {
+	let recommended = promos[format!("{}-recommended", version)].as_str();
+
+	let build = match channel {
+		ReleaseChannel::Default => recommended,
+		ReleaseChannel::Experimental => promos[format!("{}-latest", version)]
+			.as_str()
+			.or(recommended),
+	};
+
+	build.map(String::from)
+}
+
+fn pick_server_jar(files: &[String]) -> Option<String> {
+	files
+		.iter()
+		.find(|file| {
+			(file.starts_with("forge-") || file.starts_with("neoforge-"))
+				&& file.ends_with(".jar")
+				&& !file.contains("installer")
+		})
+		.cloned()
+}
+
+/// Runs `command` to completion, killing it once `limit` passes.
+async fn output_within(
+	mut command: Command,
+	installer: &str,
+	limit: Duration,
+) -> Result<Output, HttpClientError> {
+	// dropping the output future on timeout drops the child, which kills it.
+	match timeout(limit, command.kill_on_drop(true).output()).await {
+		Ok(output) => Ok(output?),
+		Err(_) => Err(HttpClientError::InstallerFailed(format!(
+			"{} did not finish within {:?}",
+			installer, limit
+		))),
+	}
+}
+
+fn output_tail(output: &Output) -> String {
+	let text: String = format!(
+		"{}{}",
+		String::from_utf8_lossy(&output.stdout),
+		String::from_utf8_lossy(&output.stderr)
+	);
+
+	let lines: Vec<&str> = text.lines().collect();
+
+	lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n")
+}
+
+/// Every `<version>` in a Maven `maven-metadata.xml`.
+async fn maven_versions(client: &Client, artifact: &str) -> Result<Vec<String>, HttpClientError> {
+	let url: String = format!("{}/maven-metadata.xml", artifact);
+	let xml: String = Upstreams::current().text(client, &url).await?;
+
+	Ok(parse_maven_versions(&xml))
+}
+
+fn parse_maven_versions(xml: &str) -> Vec<String> {
+	xml.split("<version>")
+		.skip(1)
+		.filter_map(|rest| rest.split_once("</version>"))
+		.map(|(version, _)| version.trim().to_string())
+		.collect()
+}
+
+// Maven publishes a `.sha1` next to every file.
+async fn maven_target(
+	client: &Client,
+	url: String,
+	build: String,
+) -> Result<DownloadTarget, HttpClientError> {
+	let sha1_url: String = format!("{}.sha1", url);
+
+	let sha1: Option<String> = match Upstreams::current().text(client, &sha1_url).await {
+		Ok(text) => text.split_whitespace().next().map(String::from),
+		// the installer itself is missing too, so the build doesn't exist.
+		Err(HttpClientError::UnexpectedStatus(404, _)) => {
+			return Err(HttpClientError::VersionNotFound(url));
+		}
+		Err(_) => None,
+	};
+
+	Ok(DownloadTarget {
+		url,
+		checksum: sha1.map(Checksum::Sha1),
+		build: Some(build),
+	})
+}
+
+/// `21.1.72` is Minecraft 1.21.1, `21.0.3` is plain 1.21.
+fn neoforge_minecraft_version(build: &str) -> Option<String> {
+	let mut parts = build.split('.');
+	let major: u32 = parts.next()?.parse().ok()?;
+	let minor: u32 = parts.next()?.parse().ok()?;
+
+	match minor {
+		0 => Some(format!("1.{}", major)),
+		_ => Some(format!("1.{}.{}", major, minor)),
+	}
+}
+
+fn neoforge_prefix(version: &str) -> Option<String> {
+	let mut parts = version.split('.');
+
+	if parts.next()? != "1" {
+		return None;
+	}
+
+	let major: u32 = parts.next()?.parse().ok()?;
+	let minor: u32 = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+
+	Some(format!("{}.{}.", major, minor))
+}
+
+fn sort_newest_first(versions: &mut [String]) {
+	versions.sort_by(|a, b| compare_versions(b, a));
+}
+
+// numeric parts compare as numbers so 1.20.10 sorts after 1.20.9.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+	let numbers = |version: &str| -> Vec<u64> {
+		version
+			.split(|c: char| !c.is_ascii_digit())
+			.filter_map(|part| part.parse().ok())
+			.collect()
+	};
+
+	numbers(a).cmp(&numbers(b)).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn stuck_installer_is_killed() {
+		let marker = std::env::temp_dir().join(format!("lunara-installer-{}", std::process::id()));
+
+		let mut command = Command::new("sh");
+		command
+			.arg("-c")
+			.arg(format!("sleep 2; touch {}", marker.display()));
+
+		let result = output_within(command, "installer.jar", Duration::from_millis(200)).await;
+
+		assert!(matches!(result, Err(HttpClientError::InstallerFailed(_))));
+
+		tokio::time::sleep(Duration::from_secs(3)).await;
+		assert!(!marker.exists());
+	}
+
+	#[test]
+	fn parses_maven_metadata() {
+		let xml = "<metadata><versioning><versions>\n<version>1.20.1-47.3.0</version>\n<version>1.21.1-52.0.1</version>\n</versions></versioning></metadata>";
+
+		assert_eq!(
+			parse_maven_versions(xml),
+			vec!["1.20.1-47.3.0", "1.21.1-52.0.1"]
+		);
+	}
+
+	#[test]
+	fn maps_neoforge_versions_to_minecraft() {
+		assert_eq!(
+			neoforge_minecraft_version("21.1.72").as_deref(),
+			Some("1.21.1")
+		);
+		assert_eq!(
+			neoforge_minecraft_version("21.0.3-beta").as_deref(),
+			Some("1.21")
+		);
+		assert_eq!(neoforge_prefix("1.21.1").as_deref(), Some("21.1."));
+		assert_eq!(neoforge_prefix("1.21").as_deref(), Some("21.0."));
+		assert_eq!(neoforge_prefix("24w40a"), None);
+	}
+
+	#[test]
+	fn default_channel_only_takes_recommended_forge_builds() {
+		let promos = serde_json::json!({
+			"1.20.1-latest": "47.3.12",
+			"1.20.1-recommended": "47.3.0",
+			"1.21.4-latest": "54.0.6",
+		});
+
+		assert_eq!(
+			promoted_build(&promos, "1.20.1", &ReleaseChannel::Default).as_deref(),
+			Some("47.3.0")
+		);
+		assert_eq!(
+			promoted_build(&promos, "1.20.1", &ReleaseChannel::Experimental).as_deref(),
+			Some("47.3.12")
+		);
+		assert_eq!(
+			promoted_build(&promos, "1.21.4", &ReleaseChannel::Default),
+			None
+		);
+		assert_eq!(
+			promoted_build(&promos, "1.21.4", &ReleaseChannel::Experimental).as_deref(),
+			Some("54.0.6")
+		);
+	}
+
+	#[test]
+	fn sorts_versions_numerically() {
+		let mut versions = vec![
+			String::from("1.20.9"),
+			String::from("1.20.10"),
+			String::from("1.8"),
+		];
+
+		sort_newest_first(&mut versions);
+
+		assert_eq!(versions, vec!["1.20.10", "1.20.9", "1.8"]);
+	}
+
+	#[test]
+	fn finds_args_file_in_run_script() {
+		let script = "#!/usr/bin/env sh\njava @user_jvm_args.txt @libraries/net/minecraftforge/forge/1.20.1-47.3.0/unix_args.txt \"$@\"\n";
+
+		assert_eq!(
+			args_file_from_script(script).as_deref(),
+			Some("libraries/net/minecraftforge/forge/1.20.1-47.3.0/unix_args.txt")
+		);
+		assert_eq!(args_file_from_script("java -jar server.jar"), None);
+	}
+
+	#[test]
+	fn legacy_installs_launch_the_universal_jar() {
+		let files = vec![
+			String::from("forge-1.12.2-14.23.5.2860-installer.jar.log"),
+			String::from("minecraft_server.1.12.2.jar"),
+			String::from("forge-1.12.2-14.23.5.2860.jar"),
+		];
+
+		assert_eq!(
+			pick_server_jar(&files).as_deref(),
+			Some("forge-1.12.2-14.23.5.2860.jar")
+		);
+	}
+}
@@ -14,5 +14,12 @@ See the License for the specific language governing permissions and
 limitations under the License.
 
 */
+pub(crate) mod artifact_cache;
+pub(crate) mod download;
+pub(crate) mod forge_provider;
 pub(crate) mod plugin_fetch;
+pub(crate) mod plugin_source;
 pub mod server_creator;
+pub(crate) mod server_provider;
+pub(crate) mod upstream;
+pub(crate) mod version_catalog;
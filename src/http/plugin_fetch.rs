@@ -14,6 +14,8 @@ See the License for the specific language governing permissions and
 limitations under the License.
 
 */
+use crate::http::server_creator::HttpClientError;
+use crate::http::upstream::Upstreams;
 use crate::mc::plugin::Plugin;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -55,16 +57,6 @@ struct HangarResponse {
 	result: Vec<TrendingPlugin>,
 }
 
-#[derive(Deserialize)]
-struct VersionsResponse {
-	result: Vec<VersionEntry>,
-}
-
-#[derive(Deserialize)]
-struct VersionEntry {
-	name: String,
-}
-
 #[derive(Debug, Error)]
 pub enum PluginError {
 	#[error("Unknown plugin: {0}")]
@@ -72,36 +64,40 @@ pub enum PluginError {
 
 	#[error("Authentication error")]
 	Unauthorized(String),
+
+	#[error("Upstream error: {0}")]
+	Upstream(#[from] HttpClientError),
+
+	#[error("Unexpected response: {0}")]
+	InvalidResponse(#[from] serde_json::Error),
+
+	#[error("No plugins for loader: {0}")]
+	UnsupportedLoader(String),
+
+	#[error("No matching version: {0}")]
+	NoMatchingVersion(String),
 }
 
 pub async fn fetch_trending_plugins(
 	client: &Client,
 	page: usize,
 ) -> Result<Vec<TrendingPlugin>, PluginError> {
-	let offset = page.saturating_sub(1) * CHUNK_SIZE;
-	let url = format!(
-		"https://hangar.papermc.io/api/v1/projects?sort=-stars&limit={}&offset={}",
-		CHUNK_SIZE, offset
-	);
+	let upstreams: &Upstreams = Upstreams::current();
+	let url: String = trending_url(&upstreams.hangar, page);
 
-	let resp: HangarResponse = client.get(&url).send().await?.json().await?;
+	let resp: HangarResponse = upstreams.json(client, &url).await?;
 
 	Ok(resp.result)
 }
 
-pub async fn fetch_plugin_versions(
-	client: &Client,
-	slug: &str,
-	limit: i16,
-) -> Result<Vec<String>, PluginError> {
-	let url: String = format!(
-		"https://hangar.papermc.io/api/v1/projects/{}/versions?limit={}",
-		slug, limit
-	);
-
-	let resp: VersionsResponse = client.get(&url).send().await?.json().await?;
+// pages start at 1, page 0 is the first page too.
+fn trending_url(hangar: &str, page: usize) -> String {
+	let offset = page.saturating_sub(1) * CHUNK_SIZE;
 
-	Ok(resp.result.into_iter().map(|v| v.name).collect())
+	format!(
+		"{}/projects?sort=-stars&limit={}&offset={}",
+		hangar, CHUNK_SIZE, offset
+	)
 }
 
 trait PluginCreator {
@@ -123,7 +119,7 @@ impl PluginCreator for Plugin {
 			.build()?;
 
 		Ok(client
-			.post("https://hangar.papermc.io/api/v1/authenticate")
+			.post(format!("{}/authenticate", Upstreams::current().hangar))
 			.send()
 			.await?
 			.json::<AuthHelper>()
@@ -195,10 +191,12 @@ mod tests {
 		assert_eq!(cloned.stars, stats.stars);
 	}
 
-	#[tokio::test]
-	async fn fetch_trending_plugins_with_invalid_page_returns_first_page() {
-		let client = Client::new();
-		let result = fetch_trending_plugins(&client, 0).await;
-		assert!(result.is_ok());
+	#[test]
+	fn trending_with_invalid_page_returns_first_page() {
+		let hangar: &str = &Upstreams::default().hangar;
+
+		assert_eq!(trending_url(hangar, 0), trending_url(hangar, 1));
+		assert!(trending_url(hangar, 1).ends_with("&offset=0"));
+		assert!(trending_url(hangar, 2).ends_with(&format!("&offset={}", CHUNK_SIZE)));
 	}
 }
@@ -0,0 +1,877 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::download::{Checksum, DownloadTarget};
+use crate::http::plugin_fetch::PluginError;
+use crate::http::upstream::Upstreams;
+use crate::mc::plugin::{Plugin, PluginOrigin};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const PAGE_SIZE: usize = 25;
+
+/// A project as a plugin site lists it.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct PluginProject {
+	pub source: PluginOrigin,
+	pub slug: String,
+	pub name: String,
+	pub author: String,
+	pub description: String,
+	pub downloads: u64,
+	/// Hangar stars, Modrinth followers.
+	pub stars: u64,
+	pub icon_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginSort {
+	#[default]
+	Stars,
+	Downloads,
+	Newest,
+	Updated,
+	Relevance,
+}
+
+/// What to look for, pages start at 1.
+#[derive(Debug, Clone, Default)]
+pub struct PluginSearch {
+	pub query: String,
+	pub category: Option<String>,
+	pub game_version: Option<String>,
+	pub sort: PluginSort,
+	pub page: usize,
+}
+
+/// One page of results and how many there are in total.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SearchPage {
+	pub total: u64,
+	pub page: usize,
+	pub page_size: usize,
+	pub results: Vec<PluginProject>,
+}
+
+/// One release of a project and what it runs on.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct PluginVersion {
+	pub name: String,
+	pub loaders: Vec<String>,
+	pub game_versions: Vec<String>,
+	pub published: Option<String>,
+}
+
+/// Another project a release needs, named the way its site names it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginDependency {
+	pub name: String,
+	pub required: bool,
+}
+
+/// The jar to install for a release and what else the site says it needs.
+#[derive(Debug, Clone)]
+pub struct ResolvedPlugin {
+	pub target: DownloadTarget,
+	pub dependencies: Vec<PluginDependency>,
+}
+
+/// A site plugins and mods are searched and downloaded from.
+/// `loaders` are the tags from `ServerBrand::plugin_loaders`, an empty slice doesn't filter.
+pub(crate) trait PluginSource {
+	/// Whether the site has files for any of `loaders`.
+	fn serves(&self, loaders: &[&str]) -> bool;
+
+	async fn search(
+		&self,
+		client: &Client,
+		search: &PluginSearch,
+		loaders: &[&str],
+	) -> Result<SearchPage, PluginError>;
+
+	/// Newest first.
+	async fn versions(
+		&self,
+		client: &Client,
+		project: &str,
+		loaders: &[&str],
+		game_version: Option<&str>,
+	) -> Result<Vec<PluginVersion>, PluginError>;
+
+	/// The jar to install for `plugin`, with the hash the site publishes.
+	async fn resolve(
+		&self,
+		client: &Client,
+		plugin: &Plugin,
+		loaders: &[&str],
+	) -> Result<ResolvedPlugin, PluginError>;
+}
+
+/// Every site is registered here.
+impl PluginSource for PluginOrigin {
+	fn serves(&self, loaders: &[&str]) -> bool {
+		match self {
+			PluginOrigin::Hangar => Hangar.serves(loaders),
+			PluginOrigin::Modrinth => Modrinth.serves(loaders),
+		}
+	}
+
+	async fn search(
+		&self,
+		client: &Client,
+		search: &PluginSearch,
+		loaders: &[&str],
+	) -> Result<SearchPage, PluginError> {
+		match self {
+			PluginOrigin::Hangar => Hangar.search(client, search, loaders).await,
+			PluginOrigin::Modrinth => Modrinth.search(client, search, loaders).await,
+		}
+	}
+
+	async fn versions(
+		&self,
+		client: &Client,
+		project: &str,
+		loaders: &[&str],
+		game_version: Option<&str>,
+	) -> Result<Vec<PluginVersion>, PluginError> {
+		match self {
+			PluginOrigin::Hangar => {
+				Hangar
+					.versions(client, project, loaders, game_version)
+					.await
+			}
+			PluginOrigin::Modrinth => {
+				Modrinth
+					.versions(client, project, loaders, game_version)
+					.await
+			}
+		}
+	}
+
+	async fn resolve(
+		&self,
+		client: &Client,
+		plugin: &Plugin,
+		loaders: &[&str],
+	) -> Result<ResolvedPlugin, PluginError> {
+		match self {
+			PluginOrigin::Hangar => Hangar.resolve(client, plugin, loaders).await,
+			PluginOrigin::Modrinth => Modrinth.resolve(client, plugin, loaders).await,
+		}
+	}
+}
+
+/// PaperMC's Hangar, Bukkit plugins and proxy plugins only.
+pub(crate) struct Hangar;
+
+impl PluginSource for Hangar {
+	fn serves(&self, loaders: &[&str]) -> bool {
+		hangar_platform(loaders).is_some()
+	}
+
+	async fn search(
+		&self,
+		client: &Client,
+		search: &PluginSearch,
+		loaders: &[&str],
+	) -> Result<SearchPage, PluginError> {
+		let upstreams: &Upstreams = Upstreams::current();
+		let url: String = with_query(
+			client,
+			&format!("{}/projects", upstreams.hangar),
+			&hangar_search_params(search, loaders),
+		)?;
+		let resp: Value = upstreams.json(client, &url).await?;
+
+		Ok(SearchPage {
+			total: resp["pagination"]["count"].as_u64().unwrap_or(0),
+			page: search.page.max(1),
+			page_size: PAGE_SIZE,
+			results: hangar_projects(&resp),
+		})
+	}
+
+	async fn versions(
+		&self,
+		client: &Client,
+		project: &str,
+		loaders: &[&str],
+		game_version: Option<&str>,
+	) -> Result<Vec<PluginVersion>, PluginError> {
+		let mut params: Vec<(&str, String)> = vec![("limit", PAGE_SIZE.to_string())];
+
+		if let Some(platform) = hangar_platform(loaders) {
+			params.push(("platform", platform.to_string()));
+
+			if let Some(game_version) = game_version {
+				params.push(("platformVersion", game_version.to_string()));
+			}
+		}
+
+		let upstreams: &Upstreams = Upstreams::current();
+		let url: String = with_query(
+			client,
+			&format!("{}/projects/{}/versions", upstreams.hangar, project),
+			&params,
+		)?;
+		let resp: Value = upstreams.json(client, &url).await?;
+
+		Ok(resp["result"]
+			.as_array()
+			.into_iter()
+			.flatten()
+			.filter_map(hangar_version)
+			.collect())
+	}
+
+	async fn resolve(
+		&self,
+		client: &Client,
+		plugin: &Plugin,
+		loaders: &[&str],
+	) -> Result<ResolvedPlugin, PluginError> {
+		let platform: &str = hangar_platform(loaders)
+			.ok_or_else(|| PluginError::UnsupportedLoader(loaders.join(", ")))?;
+
+		let version: Value = Upstreams::current()
+			.json(client, &plugin.version_url())
+			.await?;
+
+		let target: DownloadTarget = hangar_file(&version, plugin, platform).ok_or_else(|| {
+			PluginError::NoMatchingVersion(format!(
+				"{} {} for {}",
+				plugin.name(),
+				plugin.version(),
+				platform
+			))
+		})?;
+
+		Ok(ResolvedPlugin {
+			target,
+			dependencies: hangar_dependencies(&version, platform),
+		})
+	}
+}
+
+/// Modrinth, plugins for every server brand and mods for the modded ones.
+pub(crate) struct Modrinth;
+
+impl Modrinth {
+	async fn version_list(
+		&self,
+		client: &Client,
+		project: &str,
+		loaders: &[&str],
+		game_version: Option<&str>,
+	) -> Result<Value, PluginError> {
+		let mut params: Vec<(&str, String)> = Vec::new();
+
+		if !loaders.is_empty() {
+			params.push(("loaders", serde_json::to_string(loaders)?));
+		}
+
+		if let Some(game_version) = game_version {
+			params.push(("game_versions", serde_json::to_string(&[game_version])?));
+		}
+
+		let upstreams: &Upstreams = Upstreams::current();
+		let url: String = with_query(
+			client,
+			&format!("{}/project/{}/version", upstreams.modrinth, project),
+			&params,
+		)?;
+
+		Ok(upstreams.json(client, &url).await?)
+	}
+
+	/// Versions only list project ids, plugins are installed under their slugs.
+	async fn name_dependencies(
+		&self,
+		client: &Client,
+		dependencies: &mut [PluginDependency],
+	) -> Result<(), PluginError> {
+		if dependencies.is_empty() {
+			return Ok(());
+		}
+
+		let ids: Vec<&str> = dependencies.iter().map(|d| d.name.as_str()).collect();
+
+		let upstreams: &Upstreams = Upstreams::current();
+		let url: String = with_query(
+			client,
+			&format!("{}/projects", upstreams.modrinth),
+			&[("ids", serde_json::to_string(&ids)?)],
+		)?;
+		let projects: Value = upstreams.json(client, &url).await?;
+
+		for project in projects.as_array().into_iter().flatten() {
+			let (Some(id), Some(slug)) = (project["id"].as_str(), project["slug"].as_str()) else {
+				continue;
+			};
+
+			for dependency in dependencies.iter_mut().filter(|d| d.name == id) {
+				dependency.name = slug.to_string();
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl PluginSource for Modrinth {
+	fn serves(&self, loaders: &[&str]) -> bool {
+		!loaders.is_empty()
+	}
+
+	async fn search(
+		&self,
+		client: &Client,
+		search: &PluginSearch,
+		loaders: &[&str],
+	) -> Result<SearchPage, PluginError> {
+		let upstreams: &Upstreams = Upstreams::current();
+		let url: String = with_query(
+			client,
+			&format!("{}/search", upstreams.modrinth),
+			&modrinth_search_params(search, loaders)?,
+		)?;
+		let resp: Value = upstreams.json(client, &url).await?;
+
+		Ok(SearchPage {
+			total: resp["total_hits"].as_u64().unwrap_or(0),
+			page: search.page.max(1),
+			page_size: PAGE_SIZE,
+			results: modrinth_projects(&resp),
+		})
+	}
+
+	async fn versions(
+		&self,
+		client: &Client,
+		project: &str,
+		loaders: &[&str],
+		game_version: Option<&str>,
+	) -> Result<Vec<PluginVersion>, PluginError> {
+		let versions: Value = self
+			.version_list(client, project, loaders, game_version)
+			.await?;
+
+		Ok(versions
+			.as_array()
+			.into_iter()
+			.flatten()
+			.filter_map(|version| {
+				Some(PluginVersion {
+					name: version["version_number"].as_str()?.to_string(),
+					loaders: string_values(&version["loaders"]),
+					game_versions: string_values(&version["game_versions"]),
+					published: version["date_published"].as_str().map(String::from),
+				})
+			})
+			.collect())
+	}
+
+	async fn resolve(
+		&self,
+		client: &Client,
+		plugin: &Plugin,
+		loaders: &[&str],
+	) -> Result<ResolvedPlugin, PluginError> {
+		let versions: Value = self
+			.version_list(client, plugin.name(), loaders, None)
+			.await?;
+
+		let mut release: ResolvedPlugin = modrinth_release(&versions, plugin.version())
+			.ok_or_else(|| {
+				PluginError::NoMatchingVersion(format!(
+					"{} {} for {}",
+					plugin.name(),
+					plugin.version(),
+					loaders.join(", ")
+				))
+			})?;
+
+		self.name_dependencies(client, &mut release.dependencies)
+			.await?;
+
+		Ok(release)
+	}
+}
+
+// Hangar files Folia and the forks under PAPER too.
+fn hangar_platform(loaders: &[&str]) -> Option<&'static str> {
+	loaders.iter().find_map(|loader| match *loader {
+		"paper" | "purpur" | "folia" | "spigot" | "bukkit" => Some("PAPER"),
+		"velocity" => Some("VELOCITY"),
+		"waterfall" | "bungeecord" => Some("WATERFALL"),
+		_ => None,
+	})
+}
+
+fn page_offset(page: usize) -> usize {
+	page.saturating_sub(1) * PAGE_SIZE
+}
+
+fn hangar_search_params(search: &PluginSearch, loaders: &[&str]) -> Vec<(&'static str, String)> {
+	let mut params: Vec<(&str, String)> = vec![
+		("query", search.query.clone()),
+		("limit", PAGE_SIZE.to_string()),
+		("offset", page_offset(search.page).to_string()),
+	];
+
+	let sort: Option<&str> = match search.sort {
+		PluginSort::Stars => Some("-stars"),
+		PluginSort::Downloads => Some("-downloads"),
+		PluginSort::Newest => Some("-newest"),
+		PluginSort::Updated => Some("-updated"),
+		// Hangar ranks by relevance when no sort is given.
+		PluginSort::Relevance => None,
+	};
+
+	if let Some(sort) = sort {
+		params.push(("sort", sort.to_string()));
+	}
+
+	if let Some(category) = &search.category {
+		params.push(("category", category.clone()));
+	}
+
+	if let Some(platform) = hangar_platform(loaders) {
+		params.push(("platform", platform.to_string()));
+	}
+
+	if let Some(game_version) = &search.game_version {
+		params.push(("version", game_version.clone()));
+	}
+
+	params
+}
+
+fn modrinth_search_params(
+	search: &PluginSearch,
+	loaders: &[&str],
+) -> Result<Vec<(&'static str, String)>, PluginError> {
+	let index: &str = match search.sort {
+		PluginSort::Stars => "follows",
+		PluginSort::Downloads => "downloads",
+		PluginSort::Newest => "newest",
+		PluginSort::Updated => "updated",
+		PluginSort::Relevance => "relevance",
+	};
+
+	// facets in one inner list are OR-ed, the lists themselves AND-ed.
+	// modpacks carry loader categories too, but can't be dropped into a server.
+	let mut facets: Vec<Vec<String>> = vec![vec![String::from("project_type:mod")]];
+
+	if !loaders.is_empty() {
+		facets.push(
+			loaders
+				.iter()
+				.map(|loader| format!("categories:{}", loader))
+				.collect(),
+		);
+	}
+
+	if let Some(category) = &search.category {
+		facets.push(vec![format!("categories:{}", category)]);
+	}
+
+	if let Some(game_version) = &search.game_version {
+		facets.push(vec![format!("versions:{}", game_version)]);
+	}
+
+	let mut params: Vec<(&str, String)> = vec![
+		("query", search.query.clone()),
+		("index", index.to_string()),
+		("limit", PAGE_SIZE.to_string()),
+		("offset", page_offset(search.page).to_string()),
+	];
+
+	params.push(("facets", serde_json::to_string(&facets)?));
+
+	Ok(params)
+}
+
+fn with_query(
+	client: &Client,
+	base: &str,
+	params: &[(&str, String)],
+) -> Result<String, PluginError> {
+	Ok(client.get(base).query(params).build()?.url().to_string())
+}
+
+fn string_values(value: &Value) -> Vec<String> {
+	value
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|v| v.as_str().map(String::from))
+		.collect()
+}
+
+fn hangar_projects(resp: &Value) -> Vec<PluginProject> {
+	resp["result"]
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|project| {
+			Some(PluginProject {
+				source: PluginOrigin::Hangar,
+				slug: project["namespace"]["slug"].as_str()?.to_string(),
+				name: project["name"].as_str()?.to_string(),
+				author: project["namespace"]["owner"]
+					.as_str()
+					.unwrap_or_default()
+					.to_string(),
+				description: project["description"]
+					.as_str()
+					.unwrap_or_default()
+					.to_string(),
+				downloads: project["stats"]["downloads"].as_u64().unwrap_or(0),
+				stars: project["stats"]["stars"].as_u64().unwrap_or(0),
+				icon_url: project["avatarUrl"].as_str().map(String::from),
+			})
+		})
+		.collect()
+}
+
+fn hangar_version(version: &Value) -> Option<PluginVersion> {
+	let platforms = version["platformDependencies"].as_object()?;
+
+	let mut game_versions: Vec<String> = Vec::new();
+
+	// each platform has its own list and they can overlap.
+	for game_version in platforms.values().flat_map(string_values) {
+		if !game_versions.contains(&game_version) {
+			game_versions.push(game_version);
+		}
+	}
+
+	Some(PluginVersion {
+		name: version["name"].as_str()?.to_string(),
+		loaders: platforms.keys().map(|key| key.to_lowercase()).collect(),
+		game_versions,
+		published: version["createdAt"].as_str().map(String::from),
+	})
+}
+
+fn hangar_file(version: &Value, plugin: &Plugin, platform: &str) -> Option<DownloadTarget> {
+	let download: &Value = version["downloads"].get(platform)?;
+
+	// plugins hosted elsewhere have no file info on Hangar.
+	let checksum: Option<Checksum> = download["fileInfo"]["sha256Hash"]
+		.as_str()
+		.map(|hash| Checksum::Sha256(hash.to_string()));
+
+	Some(DownloadTarget {
+		url: download["externalUrl"]
+			.as_str()
+			.map(String::from)
+			.unwrap_or_else(|| plugin.download_url(platform)),
+		checksum,
+		build: None,
+	})
+}
+
+fn hangar_dependencies(version: &Value, platform: &str) -> Vec<PluginDependency> {
+	version["pluginDependencies"][platform]
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|dependency| {
+			Some(PluginDependency {
+				name: dependency["name"].as_str()?.to_string(),
+				required: dependency["required"].as_bool().unwrap_or(false),
+			})
+		})
+		.collect()
+}
+
+fn modrinth_projects(resp: &Value) -> Vec<PluginProject> {
+	resp["hits"]
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|hit| {
+			Some(PluginProject {
+				source: PluginOrigin::Modrinth,
+				slug: hit["slug"].as_str()?.to_string(),
+				name: hit["title"].as_str()?.to_string(),
+				author: hit["author"].as_str().unwrap_or_default().to_string(),
+				description: hit["description"].as_str().unwrap_or_default().to_string(),
+				downloads: hit["downloads"].as_u64().unwrap_or(0),
+				stars: hit["follows"].as_u64().unwrap_or(0),
+				icon_url: hit["icon_url"].as_str().map(String::from),
+			})
+		})
+		.collect()
+}
+
+/// Matches the version number users see, or Modrinth's own version id.
+fn modrinth_release(versions: &Value, version: &str) -> Option<ResolvedPlugin> {
+	let entry: &Value = versions
+		.as_array()?
+		.iter()
+		.find(|entry| entry["version_number"] == version || entry["id"] == version)?;
+
+	let files = entry["files"].as_array()?;
+	let file: &Value = files
+		.iter()
+		.find(|file| file["primary"].as_bool() == Some(true))
+		.or_else(|| files.first())?;
+
+	// the file is installed as `<name>.jar`, a modpack's `.mrpack` would break the server.
+	let url: &str = file["url"].as_str()?;
+	let file_name: &str = file["filename"].as_str().unwrap_or(url);
+
+	if !file_name.ends_with(".jar") {
+		return None;
+	}
+
+	// embedded and incompatible projects aren't installed.
+	let dependencies: Vec<PluginDependency> = entry["dependencies"]
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|dependency| {
+			let required: bool = match dependency["dependency_type"].as_str()? {
+				"required" => true,
+				"optional" => false,
+				_ => return None,
+			};
+
+			Some(PluginDependency {
+				name: dependency["project_id"].as_str()?.to_string(),
+				required,
+			})
+		})
+		.collect();
+
+	Some(ResolvedPlugin {
+		target: DownloadTarget {
+			url: url.to_string(),
+			checksum: file["hashes"]["sha1"]
+				.as_str()
+				.map(|hash| Checksum::Sha1(hash.to_string())),
+			build: None,
+		},
+		dependencies,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn hangar_platform_follows_the_loader() {
+		assert_eq!(hangar_platform(&["purpur", "paper"]), Some("PAPER"));
+		assert_eq!(hangar_platform(&["velocity"]), Some("VELOCITY"));
+		assert_eq!(hangar_platform(&["fabric"]), None);
+		assert!(!Hangar.serves(&["fabric"]));
+		assert!(Modrinth.serves(&["fabric"]));
+	}
+
+	#[test]
+	fn hangar_file_prefers_external_url_and_keeps_hash() {
+		let plugin = Plugin::new(String::from("Geyser"), String::from("2.4"));
+		let version = json!({
+			"downloads": {
+				"PAPER": {"fileInfo": {"sha256Hash": "abcd"}},
+				"VELOCITY": {"externalUrl": "https://example.com/geyser.jar"},
+			}
+		});
+
+		let paper = hangar_file(&version, &plugin, "PAPER").unwrap();
+		assert_eq!(paper.url, plugin.download_url("PAPER"));
+		assert_eq!(paper.checksum, Some(Checksum::Sha256(String::from("abcd"))));
+
+		let velocity = hangar_file(&version, &plugin, "VELOCITY").unwrap();
+		assert_eq!(velocity.url, "https://example.com/geyser.jar");
+		assert!(velocity.checksum.is_none());
+
+		assert!(hangar_file(&version, &plugin, "WATERFALL").is_none());
+	}
+
+	#[test]
+	fn modrinth_release_picks_primary_file_of_matching_version() {
+		let versions = json!([
+			{"id": "AAA", "version_number": "0.16.5", "files": [
+				{"url": "https://cdn.modrinth.com/sources.jar", "primary": false, "hashes": {"sha1": "00"}},
+				{"url": "https://cdn.modrinth.com/sodium.jar", "primary": true, "hashes": {"sha1": "11"}},
+			], "dependencies": [
+				{"project_id": "P7dR8mSH", "dependency_type": "required"},
+				{"project_id": "Ha28R6CL", "dependency_type": "optional"},
+				{"project_id": "XXXXXXXX", "dependency_type": "incompatible"},
+				{"version_id": "YYYYYYYY", "dependency_type": "required"},
+			]},
+			{"id": "BBB", "version_number": "0.16.4", "files": [
+				{"url": "https://cdn.modrinth.com/old.jar", "hashes": {"sha1": "22"}},
+			]},
+			{"id": "CCC", "version_number": "1.0-pack", "files": [
+				{"url": "https://cdn.modrinth.com/pack.mrpack", "filename": "pack.mrpack", "primary": true, "hashes": {"sha1": "33"}},
+			]},
+		]);
+
+		let latest = modrinth_release(&versions, "0.16.5").unwrap();
+		assert_eq!(latest.target.url, "https://cdn.modrinth.com/sodium.jar");
+		assert_eq!(
+			latest.target.checksum,
+			Some(Checksum::Sha1(String::from("11")))
+		);
+		assert_eq!(
+			latest.dependencies,
+			[
+				PluginDependency {
+					name: String::from("P7dR8mSH"),
+					required: true
+				},
+				PluginDependency {
+					name: String::from("Ha28R6CL"),
+					required: false
+				},
+			]
+		);
+
+		let old = modrinth_release(&versions, "BBB").unwrap();
+		assert_eq!(old.target.url, "https://cdn.modrinth.com/old.jar");
+		assert!(old.dependencies.is_empty());
+		assert!(modrinth_release(&versions, "0.1").is_none());
+		assert!(modrinth_release(&versions, "1.0-pack").is_none());
+	}
+
+	#[test]
+	fn hangar_version_lists_each_game_version_once() {
+		let version = hangar_version(&json!({
+			"name": "5.4.1",
+			"platformDependencies": {
+				"PAPER": ["1.20.4", "1.21"],
+				"WATERFALL": ["1.20"],
+				"VELOCITY": ["1.20.4"],
+			}
+		}))
+		.unwrap();
+
+		assert_eq!(version.game_versions.len(), 3);
+		assert_eq!(version.loaders.len(), 3);
+	}
+
+	#[test]
+	fn hangar_dependencies_follow_the_platform() {
+		let version = json!({
+			"pluginDependencies": {
+				"PAPER": [
+					{"name": "Vault", "required": true, "platform": "PAPER"},
+					{"name": "PlaceholderAPI", "required": false, "externalUrl": "https://example.com"},
+				],
+				"VELOCITY": [{"name": "LuckPerms", "required": true}],
+			}
+		});
+
+		let paper = hangar_dependencies(&version, "PAPER");
+
+		assert_eq!(paper.len(), 2);
+		assert!(paper[0].required && paper[0].name == "Vault");
+		assert!(!paper[1].required);
+		assert_eq!(
+			hangar_dependencies(&version, "VELOCITY")[0].name,
+			"LuckPerms"
+		);
+		assert!(hangar_dependencies(&version, "WATERFALL").is_empty());
+	}
+
+	#[test]
+	fn search_results_are_tagged_with_their_source() {
+		let hangar = hangar_projects(&json!({"result": [{
+			"name": "LuckPerms",
+			"namespace": {"owner": "Luck", "slug": "LuckPerms"},
+			"stats": {"downloads": 10, "stars": 1},
+			"avatarUrl": "https://example.com/lp.png"
+		}]}));
+		let modrinth = modrinth_projects(&json!({"hits": [{
+			"slug": "sodium",
+			"title": "Sodium",
+			"description": "Fast",
+			"downloads": 20
+		}]}));
+
+		assert_eq!(hangar[0].source, PluginOrigin::Hangar);
+		assert_eq!(hangar[0].slug, "LuckPerms");
+		assert_eq!(modrinth[0].source, PluginOrigin::Modrinth);
+		assert_eq!(modrinth[0].downloads, 20);
+		assert!(modrinth[0].icon_url.is_none());
+		assert_eq!(hangar[0].author, "Luck");
+		assert_eq!(hangar[0].stars, 1);
+	}
+
+	#[test]
+	fn hangar_search_maps_filters_onto_project_params() {
+		let search = PluginSearch {
+			query: String::from("perms"),
+			category: Some(String::from("admin_tools")),
+			game_version: Some(String::from("1.21.4")),
+			sort: PluginSort::Downloads,
+			page: 3,
+		};
+
+		let params = hangar_search_params(&search, &["purpur", "paper"]);
+
+		assert!(params.contains(&("offset", String::from("50"))));
+		assert!(params.contains(&("sort", String::from("-downloads"))));
+		assert!(params.contains(&("category", String::from("admin_tools"))));
+		assert!(params.contains(&("platform", String::from("PAPER"))));
+		assert!(params.contains(&("version", String::from("1.21.4"))));
+
+		let relevance = PluginSearch {
+			sort: PluginSort::Relevance,
+			..PluginSearch::default()
+		};
+		let params = hangar_search_params(&relevance, &[]);
+
+		assert!(
+			params
+				.iter()
+				.all(|(key, _)| *key != "sort" && *key != "platform")
+		);
+		assert!(params.contains(&("offset", String::from("0"))));
+	}
+
+	#[test]
+	fn modrinth_search_ands_filters_and_ors_loaders() {
+		let search = PluginSearch {
+			category: Some(String::from("optimization")),
+			game_version: Some(String::from("1.21.1")),
+			..PluginSearch::default()
+		};
+
+		let params = modrinth_search_params(&search, &["fabric", "quilt"]).unwrap();
+
+		assert!(params.contains(&("index", String::from("follows"))));
+		assert!(params.contains(&(
+			"facets",
+			String::from(
+				r#"[["project_type:mod"],["categories:fabric","categories:quilt"],["categories:optimization"],["versions:1.21.1"]]"#
+			)
+		)));
+
+		let params = modrinth_search_params(&PluginSearch::default(), &[]).unwrap();
+		assert!(params.contains(&("facets", String::from(r#"[["project_type:mod"]]"#))));
+	}
+}
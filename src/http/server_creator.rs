@@ -1,4 +1,9 @@
-use crate::mc::server::{BuildInfo, ServerBrand, MinecraftServer};
+use crate::http::artifact_cache::ArtifactCache;
+use crate::http::download::{DownloadProgress, DownloadTarget};
+use crate::http::server_provider::ServerProvider;
+use crate::http::upstream::Upstreams;
+use crate::mc::launch_profile::LaunchTarget;
+use crate::mc::server::{BuildInfo, MinecraftServer};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use reqwest::Client;
@@ -6,8 +11,7 @@ use serde_json::Value;
 use std::io::Error;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
 
 #[tokio::test]
 async fn handles_invalid_version_correctly() {
@@ -15,10 +19,13 @@ async fn handles_invalid_version_correctly() {
 
 	builder.with_version(BuildInfo {
 		version: String::from("None"),
+		..Default::default()
 	});
 
 	let server = builder.build();
-	let url = server.resolve_paper_url(&Client::new()).await;
+	let url = crate::http::server_provider::PaperMc::PAPER
+		.resolve_artifact(&Client::new(), server.build_info())
+		.await;
 
 	assert!(url.is_err())
 }
@@ -29,17 +36,57 @@ async fn handles_invalid_download_dir() {
 
 	builder.with_version(BuildInfo {
 		version: String::from("1.21.1"),
+		..Default::default()
 	});
 
 	let server = builder.build();
+	let cache = ArtifactCache::new(
+		std::env::temp_dir().join(format!("lunara-creator-cache-{}", std::process::id())),
+		1 << 30,
+	);
 
 	let result = server
-		.try_download("/nonexistent/path/that/cannot/exist")
+		.try_download(&cache, "/nonexistent/path/that/cannot/exist", None)
 		.await;
 
 	assert!(result.is_err())
 }
 
+#[test]
+fn resolves_version_aliases() {
+	let manifest = serde_json::json!({
+		"latest": {"release": "1.21.1", "snapshot": "24w40a"},
+		"versions": [],
+	});
+
+	assert_eq!(resolve_version_id(&manifest, "latest").unwrap(), "1.21.1");
+	assert_eq!(
+		resolve_version_id(&manifest, "latest-snapshot").unwrap(),
+		"24w40a"
+	);
+	assert_eq!(resolve_version_id(&manifest, "1.19.2").unwrap(), "1.19.2");
+}
+
+#[test]
+fn rejects_versions_without_server_download() {
+	let old =
+		serde_json::json!({"downloads": {"client": {"url": "https://example.com/client.jar"}}});
+	let modern =
+		serde_json::json!({"downloads": {"server": {"url": "https://example.com/server.jar"}}});
+
+	assert!(matches!(
+		server_download(&old, "1.0"),
+		Err(HttpClientError::VersionNotFound(_))
+	));
+	assert_eq!(
+		server_download(&modern, "1.21.1").unwrap()["url"],
+		"https://example.com/server.jar"
+	);
+}
+
+pub(crate) const LATEST_RELEASE: &str = "latest";
+const LATEST_SNAPSHOT: &str = "latest-snapshot";
+
 #[derive(Debug, Error)]
 pub enum HttpClientError {
 	#[error("Failed to fetch manifest: {0}")]
@@ -50,6 +97,12 @@ pub enum HttpClientError {
 	VersionNotFound(String),
 	#[error("File operation failed: {0}")]
 	FileError(#[from] Error),
+	#[error("Checksum mismatch: expected {expected}, got {actual}")]
+	ChecksumMismatch { expected: String, actual: String },
+	#[error("Unexpected HTTP status {0} from {1}")]
+	UnexpectedStatus(u16, String),
+	#[error("Installer failed: {0}")]
+	InstallerFailed(String),
 }
 
 impl IntoResponse for HttpClientError {
@@ -62,111 +115,148 @@ impl IntoResponse for HttpClientError {
 				format!("Invalid manifest. Unknown manifest {}", manifest)
 			}
 			HttpClientError::FileError(error) => format!("File error: {}", error),
+			HttpClientError::ChecksumMismatch { expected, actual } => format!(
+				"Download is corrupt. Expected checksum {} but got {}",
+				expected, actual
+			),
+			HttpClientError::UnexpectedStatus(status, url) => {
+				format!("Download failed with status {} from {}", status, url)
+			}
+			HttpClientError::InstallerFailed(reason) => {
+				format!("Server installer failed. {}", reason)
+			}
 			_ => String::new(),
 		};
 		(StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
 	}
 }
 
+/// What an install left behind: how to launch it and which upstream build it was.
+pub(crate) struct Installed {
+	pub launch: LaunchTarget,
+	pub build: Option<String>,
+}
+
 pub(crate) trait ServerCreator {
-	async fn resolve_paper_url(&self, client: &Client) -> Result<String, HttpClientError>;
-	async fn resolve_vanilla_url(&self, client: &Client) -> Result<String, HttpClientError>;
-	async fn resolve_download_url(&self, client: &Client) -> Result<String, HttpClientError>;
-	async fn try_download(&self, dir_builder: &str) -> Result<String, HttpClientError>;
+	async fn resolve_download_url(
+		&self,
+		client: &Client,
+	) -> Result<DownloadTarget, HttpClientError>;
+	async fn resolve_java_major(&self, client: &Client) -> Result<u32, HttpClientError>;
+	async fn try_download(
+		&self,
+		cache: &ArtifactCache,
+		dir_path: &str,
+		progress: Option<&watch::Sender<DownloadProgress>>,
+	) -> Result<Installed, HttpClientError>;
 }
 
 impl ServerCreator for MinecraftServer {
-	async fn resolve_paper_url(&self, client: &Client) -> Result<String, HttpClientError> {
-		let mc_version = &self.build().version();
-		let builds_url = format!(
-			"https://api.papermc.io/v2/projects/paper/versions/{}/builds",
-			mc_version
-		);
-
-		let resp: Value = client.get(builds_url).send().await?.json().await?;
-
-		let builds = resp["builds"]
-			.as_array()
-			.ok_or(HttpClientError::InvalidManifest("missing builds array"))?;
-
-		let latest_build = builds
-			.last()
-			.ok_or_else(|| HttpClientError::VersionNotFound(mc_version.to_string()))?;
-
-		let build_id = latest_build["build"]
-			.as_u64()
-			.ok_or(HttpClientError::InvalidManifest("missing build id"))?;
-
-		let file_name = latest_build["downloads"]["application"]["name"]
-			.as_str()
-			.ok_or(HttpClientError::InvalidManifest(
-				"missing download_url file name",
-			))?;
-
-		Ok(format!(
-			"https://api.papermc.io/v2/projects/paper/versions/{}/builds/{}/downloads/{}",
-			mc_version, build_id, file_name
-		))
+	async fn resolve_download_url(
+		&self,
+		client: &Client,
+	) -> Result<DownloadTarget, HttpClientError> {
+		self.brand()
+			.resolve_artifact(client, self.build_info())
+			.await
 	}
 
-	async fn resolve_vanilla_url(&self, client: &Client) -> Result<String, HttpClientError> {
-		let manifest: Value = client
-			.get("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json")
-			.send()
-			.await?
-			.json()
-			.await?;
+	async fn resolve_java_major(&self, client: &Client) -> Result<u32, HttpClientError> {
+		self.brand().java_major(client, self.version()).await
+	}
 
-		let latest_id = manifest["latest"]["release"]
-			.as_str()
-			.ok_or(HttpClientError::InvalidManifest("missing latest release"))?;
+	async fn try_download(
+		&self,
+		cache: &ArtifactCache,
+		dir_path: &str,
+		progress: Option<&watch::Sender<DownloadProgress>>,
+	) -> Result<Installed, HttpClientError> {
+		let client: Client = Client::new();
+		let target: DownloadTarget = self.resolve_download_url(&client).await?;
 
-		let versions = manifest["versions"]
-			.as_array()
-			.ok_or(HttpClientError::InvalidManifest("missing versions array"))?;
+		let file_name: &str = target.file_name();
 
-		let version_entry = versions
-			.iter()
-			.find(|v| v["id"] == latest_id)
-			.ok_or_else(|| HttpClientError::VersionNotFound(latest_id.to_string()))?;
+		let path: &Path = Path::new(dir_path);
+		let mut path_buf: PathBuf = PathBuf::from(path);
+
+		path_buf.push(file_name);
 
-		let version_url = version_entry["url"]
-			.as_str()
-			.ok_or(HttpClientError::InvalidManifest("missing version url"))?;
+		cache.fetch(&client, &target, &path_buf, progress).await?;
 
-		let version_data: Value = client.get(version_url).send().await?.json().await?;
+		let launch: LaunchTarget = self
+			.brand()
+			.post_install(&client, self.version(), path, file_name)
+			.await?;
 
-		version_data["downloads"]["server"]["url"]
-			.as_str()
-			.map(String::from)
-			.ok_or(HttpClientError::InvalidManifest(
-				"missing server download_url url",
-			))
+		Ok(Installed {
+			launch,
+			build: target.build,
+		})
 	}
+}
 
-	async fn resolve_download_url(&self, client: &Client) -> Result<String, HttpClientError> {
-		match self.clone().brand() {
-			ServerBrand::Vanilla => self.resolve_vanilla_url(client).await,
-			ServerBrand::Paper => self.resolve_paper_url(client).await,
-		}
+/// Turns `latest` or `latest-snapshot` into a concrete version id.
+pub(crate) async fn resolve_vanilla_version(
+	client: &Client,
+	requested: &str,
+) -> Result<String, HttpClientError> {
+	if requested != LATEST_RELEASE && requested != LATEST_SNAPSHOT {
+		return Ok(requested.to_string());
 	}
 
-	async fn try_download(&self, dir_path: &str) -> Result<String, HttpClientError> {
-		let client: Client = Client::new();
-		let url: String = self.resolve_download_url(&client).await?;
+	let manifest: Value = fetch_version_manifest(client).await?;
 
-		let file_name: &str = url.split('/').next_back().unwrap_or("server.jar");
+	resolve_version_id(&manifest, requested).map(String::from)
+}
 
-		let path: &Path = Path::new(dir_path);
-		let mut path_buf: PathBuf = PathBuf::from(path);
+pub(crate) fn resolve_version_id<'a>(
+	manifest: &'a Value,
+	requested: &'a str,
+) -> Result<&'a str, HttpClientError> {
+	let channel: &str = match requested {
+		LATEST_RELEASE => "release",
+		LATEST_SNAPSHOT => "snapshot",
+		_ => return Ok(requested),
+	};
+
+	manifest["latest"][channel]
+		.as_str()
+		.ok_or(HttpClientError::InvalidManifest("missing latest version"))
+}
 
-		path_buf.push(file_name);
+// versions before 1.2.5 only ever shipped a client jar.
+pub(crate) fn server_download<'a>(
+	version_data: &'a Value,
+	version_id: &str,
+) -> Result<&'a Value, HttpClientError> {
+	version_data["downloads"]
+		.get("server")
+		.ok_or_else(|| HttpClientError::VersionNotFound(version_id.to_string()))
+}
 
-		let response = reqwest::get(&url).await?;
-		let mut file = fs::File::create(path_buf).await?;
-		let bytes = response.bytes().await?;
-		file.write_all(&bytes).await?;
+pub(crate) async fn fetch_version_manifest(client: &Client) -> Result<Value, HttpClientError> {
+	let upstreams: &Upstreams = Upstreams::current();
 
-		Ok(file_name.to_string())
-	}
+	upstreams.json(client, &upstreams.mojang_manifest).await
+}
+
+pub(crate) async fn fetch_version_data(
+	client: &Client,
+	manifest: &Value,
+	version_id: &str,
+) -> Result<Value, HttpClientError> {
+	let versions = manifest["versions"]
+		.as_array()
+		.ok_or(HttpClientError::InvalidManifest("missing versions array"))?;
+
+	let version_entry = versions
+		.iter()
+		.find(|v| v["id"] == version_id)
+		.ok_or_else(|| HttpClientError::VersionNotFound(version_id.to_string()))?;
+
+	let version_url = version_entry["url"]
+		.as_str()
+		.ok_or(HttpClientError::InvalidManifest("missing version url"))?;
+
+	Upstreams::current().json(client, version_url).await
 }
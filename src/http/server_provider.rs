@@ -0,0 +1,919 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::download::{Checksum, DownloadTarget};
+use crate::http::forge_provider::{Forge, NeoForge};
+use crate::http::server_creator::{
+	HttpClientError, LATEST_RELEASE, fetch_version_data, fetch_version_manifest,
+	resolve_vanilla_version, resolve_version_id, server_download,
+};
+use crate::http::upstream::Upstreams;
+use crate::mc::java_runtime::fallback_major;
+use crate::mc::launch_profile::LaunchTarget;
+use crate::mc::server::{BuildInfo, ReleaseChannel, ServerBrand};
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+// Velocity 3.4 and up need Java 21, Waterfall is happy with it too.
+const PROXY_JAVA_MAJOR: u32 = 21;
+
+/// One entry of a brand's version list. Dates are whatever the upstream API returned.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CatalogVersion {
+	pub id: String,
+	pub release_type: String,
+	pub released: Option<String>,
+	pub java_major: Option<u32>,
+}
+
+impl CatalogVersion {
+	pub(crate) fn from_id(id: String) -> Self {
+		Self {
+			release_type: release_type(&id).to_string(),
+			id,
+			released: None,
+			java_major: None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CatalogBuild {
+	pub id: String,
+	pub channel: Option<String>,
+	pub released: Option<String>,
+}
+
+impl CatalogBuild {
+	pub(crate) fn from_id(id: String) -> Self {
+		Self {
+			id,
+			channel: None,
+			released: None,
+		}
+	}
+}
+
+/// Where a server brand's versions and jars come from.
+/// Versions and builds are listed newest first.
+pub(crate) trait ServerProvider {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError>;
+
+	async fn builds(
+		&self,
+		client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError>;
+
+	/// `build` picks a specific build, `None` means the newest one.
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError>;
+
+	/// Runs once the jar is in `dir` and says what to launch. Most brands just run the jar.
+	async fn post_install(
+		&self,
+		_client: &Client,
+		_version: &str,
+		_dir: &Path,
+		jar: &str,
+	) -> Result<LaunchTarget, HttpClientError> {
+		Ok(LaunchTarget::Jar(jar.to_string()))
+	}
+
+	/// Turns `latest` into the newest listed version.
+	async fn resolve_version(
+		&self,
+		client: &Client,
+		requested: &str,
+	) -> Result<String, HttpClientError> {
+		if requested != LATEST_RELEASE {
+			return Ok(requested.to_string());
+		}
+
+		self.versions(client)
+			.await?
+			.into_iter()
+			.next()
+			.map(|version| version.id)
+			.ok_or_else(|| HttpClientError::VersionNotFound(requested.to_string()))
+	}
+
+	/// Reads `javaVersion.majorVersion` from Mojang's version JSON, which fits every
+	/// brand that shares the vanilla version ids.
+	async fn java_major(&self, client: &Client, version: &str) -> Result<u32, HttpClientError> {
+		let manifest: Value = fetch_version_manifest(client).await?;
+		let version_id: &str = resolve_version_id(&manifest, version)?;
+		let version_data: Value = fetch_version_data(client, &manifest, version_id).await?;
+
+		// versions before 1.17 predate the field and all run on Java 8.
+		match version_data.get("javaVersion") {
+			None => Ok(8),
+			Some(java) => java["majorVersion"]
+				.as_u64()
+				.map(|major| major as u32)
+				.ok_or(HttpClientError::InvalidManifest(
+					"missing java major version",
+				)),
+		}
+	}
+
+	/// Cheap guess for version lists, where looking up every version's metadata is too slow.
+	fn java_estimate(&self, version: &str) -> Option<u32> {
+		version.starts_with("1.").then(|| fallback_major(version))
+	}
+
+	/// Loader tags plugin sites use for files this brand can load, most specific first.
+	fn plugin_loaders(&self) -> &'static [&'static str] {
+		&[]
+	}
+
+	/// Where plugins or mods go inside the server directory.
+	fn plugin_dir(&self) -> &'static str {
+		"plugins"
+	}
+}
+
+impl ServerBrand {
+	/// Every brand maps to its provider here, nothing else needs to know which API a brand uses.
+	pub(crate) fn provider(&self) -> Provider {
+		match self {
+			ServerBrand::Vanilla => Provider::Mojang(Mojang),
+			ServerBrand::Paper => Provider::PaperMc(PaperMc::PAPER),
+			ServerBrand::Folia => Provider::PaperMc(PaperMc::FOLIA),
+			ServerBrand::Velocity => Provider::PaperMc(PaperMc::VELOCITY),
+			ServerBrand::Waterfall => Provider::PaperMc(PaperMc::WATERFALL),
+			ServerBrand::Purpur => Provider::Purpur(Purpur),
+			ServerBrand::Fabric => Provider::Fabric(Fabric),
+			ServerBrand::Forge => Provider::Forge(Forge),
+			ServerBrand::NeoForge => Provider::NeoForge(NeoForge),
+		}
+	}
+}
+
+/// A brand's provider. An enum rather than `dyn ServerProvider`, async trait methods aren't object safe.
+pub(crate) enum Provider {
+	Mojang(Mojang),
+	PaperMc(PaperMc),
+	Purpur(Purpur),
+	Fabric(Fabric),
+	Forge(Forge),
+	NeoForge(NeoForge),
+}
+
+// calls the same method on whichever provider is inside.
+macro_rules! dispatch {
+	($provider:expr, $inner:ident => $call:expr) => {
+		match $provider {
+			Provider::Mojang($inner) => $call,
+			Provider::PaperMc($inner) => $call,
+			Provider::Purpur($inner) => $call,
+			Provider::Fabric($inner) => $call,
+			Provider::Forge($inner) => $call,
+			Provider::NeoForge($inner) => $call,
+		}
+	};
+}
+
+impl ServerProvider for Provider {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		let versions: Vec<CatalogVersion> = dispatch!(self, p => p.versions(client).await)?;
+
+		Ok(versions
+			.into_iter()
+			.map(|version| CatalogVersion {
+				java_major: version
+					.java_major
+					.or_else(|| self.java_estimate(&version.id)),
+				..version
+			})
+			.collect())
+	}
+
+	async fn builds(
+		&self,
+		client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError> {
+		dispatch!(self, p => p.builds(client, version).await)
+	}
+
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError> {
+		dispatch!(self, p => p.resolve_artifact(client, info).await)
+	}
+
+	async fn post_install(
+		&self,
+		client: &Client,
+		version: &str,
+		dir: &Path,
+		jar: &str,
+	) -> Result<LaunchTarget, HttpClientError> {
+		dispatch!(self, p => p.post_install(client, version, dir, jar).await)
+	}
+
+	async fn resolve_version(
+		&self,
+		client: &Client,
+		requested: &str,
+	) -> Result<String, HttpClientError> {
+		dispatch!(self, p => p.resolve_version(client, requested).await)
+	}
+
+	async fn java_major(&self, client: &Client, version: &str) -> Result<u32, HttpClientError> {
+		dispatch!(self, p => p.java_major(client, version).await)
+	}
+
+	fn java_estimate(&self, version: &str) -> Option<u32> {
+		dispatch!(self, p => p.java_estimate(version))
+	}
+
+	fn plugin_loaders(&self) -> &'static [&'static str] {
+		dispatch!(self, p => p.plugin_loaders())
+	}
+
+	fn plugin_dir(&self) -> &'static str {
+		dispatch!(self, p => p.plugin_dir())
+	}
+}
+
+impl ServerProvider for ServerBrand {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		self.provider().versions(client).await
+	}
+
+	async fn builds(
+		&self,
+		client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError> {
+		self.provider().builds(client, version).await
+	}
+
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError> {
+		self.provider().resolve_artifact(client, info).await
+	}
+
+	async fn post_install(
+		&self,
+		client: &Client,
+		version: &str,
+		dir: &Path,
+		jar: &str,
+	) -> Result<LaunchTarget, HttpClientError> {
+		self.provider()
+			.post_install(client, version, dir, jar)
+			.await
+	}
+
+	async fn resolve_version(
+		&self,
+		client: &Client,
+		requested: &str,
+	) -> Result<String, HttpClientError> {
+		self.provider().resolve_version(client, requested).await
+	}
+
+	async fn java_major(&self, client: &Client, version: &str) -> Result<u32, HttpClientError> {
+		self.provider().java_major(client, version).await
+	}
+
+	fn java_estimate(&self, version: &str) -> Option<u32> {
+		self.provider().java_estimate(version)
+	}
+
+	fn plugin_loaders(&self) -> &'static [&'static str] {
+		self.provider().plugin_loaders()
+	}
+
+	fn plugin_dir(&self) -> &'static str {
+		self.provider().plugin_dir()
+	}
+}
+
+/// Vanilla jars straight from Mojang's piston-meta.
+pub(crate) struct Mojang;
+
+impl ServerProvider for Mojang {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		let manifest: Value = fetch_version_manifest(client).await?;
+
+		Ok(manifest["versions"]
+			.as_array()
+			.into_iter()
+			.flatten()
+			.filter_map(|entry| {
+				Some(CatalogVersion {
+					id: entry["id"].as_str()?.to_string(),
+					release_type: entry["type"].as_str().unwrap_or("release").to_string(),
+					released: entry["releaseTime"].as_str().map(String::from),
+					java_major: None,
+				})
+			})
+			.collect())
+	}
+
+	// Mojang ships exactly one jar per version.
+	async fn builds(
+		&self,
+		_client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError> {
+		Ok(vec![CatalogBuild::from_id(version.to_string())])
+	}
+
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError> {
+		let version: &str = &info.version;
+		let manifest: Value = fetch_version_manifest(client).await?;
+		let version_id: &str = resolve_version_id(&manifest, version)?;
+
+		let version_data: Value = fetch_version_data(client, &manifest, version_id).await?;
+
+		let server = server_download(&version_data, version_id)?;
+
+		let url = server["url"]
+			.as_str()
+			.ok_or(HttpClientError::InvalidManifest(
+				"missing server download_url url",
+			))?;
+
+		let sha1 = server["sha1"]
+			.as_str()
+			.ok_or(HttpClientError::InvalidManifest("missing sha1 checksum"))?;
+
+		Ok(DownloadTarget {
+			url: url.to_string(),
+			checksum: Some(Checksum::Sha1(sha1.to_string())),
+			build: None,
+		})
+	}
+
+	/// Knows `latest-snapshot` too.
+	async fn resolve_version(
+		&self,
+		client: &Client,
+		requested: &str,
+	) -> Result<String, HttpClientError> {
+		resolve_vanilla_version(client, requested).await
+	}
+}
+
+/// Paper, Folia and the Velocity and Waterfall proxies, which all live on the PaperMC API.
+pub(crate) struct PaperMc {
+	project: &'static str,
+	loaders: &'static [&'static str],
+	proxy: bool,
+}
+
+impl PaperMc {
+	pub(crate) const PAPER: PaperMc = PaperMc {
+		project: "paper",
+		loaders: &["paper", "spigot", "bukkit"],
+		proxy: false,
+	};
+	pub(crate) const FOLIA: PaperMc = PaperMc {
+		project: "folia",
+		loaders: &["folia"],
+		proxy: false,
+	};
+	pub(crate) const VELOCITY: PaperMc = PaperMc {
+		project: "velocity",
+		loaders: &["velocity"],
+		proxy: true,
+	};
+	pub(crate) const WATERFALL: PaperMc = PaperMc {
+		project: "waterfall",
+		loaders: &["waterfall", "bungeecord"],
+		proxy: true,
+	};
+
+	async fn build_list(&self, client: &Client, version: &str) -> Result<Value, HttpClientError> {
+		let builds_url = format!(
+			"{}/{}/versions/{}/builds",
+			Upstreams::current().papermc,
+			self.project,
+			version
+		);
+
+		let resp: Value = Upstreams::current().json(client, &builds_url).await?;
+
+		match resp.get("builds") {
+			Some(builds) if builds.is_array() => Ok(builds.clone()),
+			_ => Err(HttpClientError::VersionNotFound(version.to_string())),
+		}
+	}
+}
+
+impl ServerProvider for PaperMc {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		let resp: Value = Upstreams::current()
+			.json(
+				client,
+				&format!("{}/{}", Upstreams::current().papermc, self.project),
+			)
+			.await?;
+
+		Ok(string_values(&resp["versions"])
+			.into_iter()
+			.rev()
+			.map(CatalogVersion::from_id)
+			.collect())
+	}
+
+	async fn builds(
+		&self,
+		client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError> {
+		let builds: Value = self.build_list(client, version).await?;
+
+		Ok(builds
+			.as_array()
+			.into_iter()
+			.flatten()
+			.rev()
+			.filter_map(|build| {
+				Some(CatalogBuild {
+					id: build["build"].as_u64()?.to_string(),
+					channel: build["channel"].as_str().map(|c| c.to_ascii_lowercase()),
+					released: build["time"].as_str().map(String::from),
+				})
+			})
+			.collect())
+	}
+
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError> {
+		let version: &str = &info.version;
+		let builds: Value = self.build_list(client, version).await?;
+		let builds = builds
+			.as_array()
+			.ok_or(HttpClientError::InvalidManifest("missing builds array"))?;
+
+		let chosen = match info.build.as_deref() {
+			Some(build) => builds.iter().find(|candidate| {
+				candidate["build"].as_u64().map(|id| id.to_string()) == Some(build.to_string())
+			}),
+			None => builds
+				.iter()
+				.rev()
+				.find(|candidate| info.channel.accepts(candidate["channel"].as_str())),
+		}
+		.ok_or_else(|| {
+			HttpClientError::VersionNotFound(match info.build.as_deref() {
+				Some(build) => format!("{} build {}", version, build),
+				None => format!("{} has no {} builds", version, info.channel),
+			})
+		})?;
+
+		let build_id = chosen["build"]
+			.as_u64()
+			.ok_or(HttpClientError::InvalidManifest("missing build id"))?;
+
+		let application = &chosen["downloads"]["application"];
+
+		let file_name = application["name"]
+			.as_str()
+			.ok_or(HttpClientError::InvalidManifest(
+				"missing download_url file name",
+			))?;
+
+		let sha256 = application["sha256"]
+			.as_str()
+			.ok_or(HttpClientError::InvalidManifest("missing sha256 checksum"))?;
+
+		Ok(DownloadTarget {
+			url: format!(
+				"{}/{}/versions/{}/builds/{}/downloads/{}",
+				Upstreams::current().papermc,
+				self.project,
+				version,
+				build_id,
+				file_name
+			),
+			checksum: Some(Checksum::Sha256(sha256.to_string())),
+			build: Some(build_id.to_string()),
+		})
+	}
+
+	async fn java_major(&self, client: &Client, version: &str) -> Result<u32, HttpClientError> {
+		if self.proxy {
+			return Ok(PROXY_JAVA_MAJOR);
+		}
+
+		Mojang.java_major(client, version).await
+	}
+
+	fn java_estimate(&self, version: &str) -> Option<u32> {
+		if self.proxy {
+			return Some(PROXY_JAVA_MAJOR);
+		}
+
+		Mojang.java_estimate(version)
+	}
+
+	fn plugin_loaders(&self) -> &'static [&'static str] {
+		self.loaders
+	}
+}
+
+/// Purpur only publishes md5 sums.
+pub(crate) struct Purpur;
+
+impl Purpur {
+	async fn build(
+		&self,
+		client: &Client,
+		version: &str,
+		build: &str,
+	) -> Result<Value, HttpClientError> {
+		Upstreams::current()
+			.json(
+				client,
+				&format!("{}/{}/{}", Upstreams::current().purpur, version, build),
+			)
+			.await
+	}
+}
+
+impl ServerProvider for Purpur {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		let upstreams: &Upstreams = Upstreams::current();
+		let resp: Value = upstreams.json(client, &upstreams.purpur).await?;
+
+		Ok(string_values(&resp["versions"])
+			.into_iter()
+			.rev()
+			.map(CatalogVersion::from_id)
+			.collect())
+	}
+
+	async fn builds(
+		&self,
+		client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError> {
+		let resp: Value = Upstreams::current()
+			.json(
+				client,
+				&format!("{}/{}", Upstreams::current().purpur, version),
+			)
+			.await?;
+
+		let builds = string_values(&resp["builds"]["all"]);
+
+		if builds.is_empty() {
+			return Err(HttpClientError::VersionNotFound(version.to_string()));
+		}
+
+		Ok(builds
+			.into_iter()
+			.rev()
+			.map(CatalogBuild::from_id)
+			.collect())
+	}
+
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError> {
+		let version: &str = &info.version;
+
+		let resp: Value = match (info.build.as_deref(), &info.channel) {
+			(Some(build), _) => self.build(client, version, build).await?,
+			(None, ReleaseChannel::Experimental) => self.build(client, version, "latest").await?,
+			// purpur has no channels, only builds that failed upstream are left out.
+			(None, ReleaseChannel::Default) => {
+				let mut stable: Option<Value> = None;
+
+				for build in self.builds(client, version).await? {
+					let resp: Value = self.build(client, version, &build.id).await?;
+
+					if resp["result"]
+						.as_str()
+						.is_none_or(|result| result == "SUCCESS")
+					{
+						stable = Some(resp);
+						break;
+					}
+				}
+
+				stable.ok_or_else(|| {
+					HttpClientError::VersionNotFound(format!(
+						"{} has no {} builds",
+						version, info.channel
+					))
+				})?
+			}
+		};
+
+		let build_id = resp["build"].as_str().ok_or_else(|| {
+			HttpClientError::VersionNotFound(format!(
+				"{} build {}",
+				version,
+				info.build.as_deref().unwrap_or("latest")
+			))
+		})?;
+
+		Ok(DownloadTarget {
+			url: format!(
+				"{}/{}/{}/download",
+				Upstreams::current().purpur,
+				version,
+				build_id
+			),
+			checksum: resp["md5"]
+				.as_str()
+				.map(|hash| Checksum::Md5(hash.to_string())),
+			build: Some(build_id.to_string()),
+		})
+	}
+
+	fn plugin_loaders(&self) -> &'static [&'static str] {
+		&["purpur", "paper", "spigot", "bukkit"]
+	}
+}
+
+/// Fabric's server launcher from the meta API, it fetches the vanilla jar and loader on first start.
+/// Builds are loader versions.
+pub(crate) struct Fabric;
+
+impl Fabric {
+	async fn latest_installer(&self, client: &Client) -> Result<String, HttpClientError> {
+		let installers: Value = Upstreams::current()
+			.json(
+				client,
+				&format!("{}/installer", Upstreams::current().fabric_meta),
+			)
+			.await?;
+
+		first_stable(&installers, "version")
+			.ok_or(HttpClientError::InvalidManifest("no fabric installer"))
+	}
+}
+
+impl ServerProvider for Fabric {
+	async fn versions(&self, client: &Client) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		let games: Value = Upstreams::current()
+			.json(
+				client,
+				&format!("{}/game", Upstreams::current().fabric_meta),
+			)
+			.await?;
+
+		Ok(games
+			.as_array()
+			.into_iter()
+			.flatten()
+			.filter_map(|entry| {
+				let stable: bool = entry["stable"].as_bool().unwrap_or(false);
+
+				Some(CatalogVersion {
+					id: entry["version"].as_str()?.to_string(),
+					release_type: String::from(if stable { "release" } else { "snapshot" }),
+					released: None,
+					java_major: None,
+				})
+			})
+			.collect())
+	}
+
+	async fn builds(
+		&self,
+		client: &Client,
+		version: &str,
+	) -> Result<Vec<CatalogBuild>, HttpClientError> {
+		let loaders: Value = Upstreams::current()
+			.json(
+				client,
+				&format!("{}/loader/{}", Upstreams::current().fabric_meta, version),
+			)
+			.await?;
+
+		let builds: Vec<CatalogBuild> = loaders
+			.as_array()
+			.into_iter()
+			.flatten()
+			.filter_map(|entry| {
+				let loader = &entry["loader"];
+				let stable: bool = loader["stable"].as_bool().unwrap_or(false);
+
+				Some(CatalogBuild {
+					id: loader["version"].as_str()?.to_string(),
+					channel: Some(String::from(if stable { "stable" } else { "beta" })),
+					released: None,
+				})
+			})
+			.collect();
+
+		if builds.is_empty() {
+			return Err(HttpClientError::VersionNotFound(version.to_string()));
+		}
+
+		Ok(builds)
+	}
+
+	async fn resolve_artifact(
+		&self,
+		client: &Client,
+		info: &BuildInfo,
+	) -> Result<DownloadTarget, HttpClientError> {
+		let version: &str = &info.version;
+		let loader: String = match info.build.as_deref() {
+			Some(loader) => loader.to_string(),
+			None => self
+				.builds(client, version)
+				.await?
+				.into_iter()
+				.find(|build| info.channel.accepts(build.channel.as_deref()))
+				.map(|build| build.id)
+				.ok_or_else(|| {
+					HttpClientError::VersionNotFound(format!(
+						"{} has no {} loaders",
+						version, info.channel
+					))
+				})?,
+		};
+
+		let installer: String = self.latest_installer(client).await?;
+
+		// no checksum is published for the launcher.
+		Ok(DownloadTarget {
+			url: format!(
+				"{}/loader/{}/{}/{}/server/jar",
+				Upstreams::current().fabric_meta,
+				version,
+				loader,
+				installer
+			),
+			checksum: None,
+			build: Some(loader),
+		})
+	}
+
+	async fn resolve_version(
+		&self,
+		client: &Client,
+		requested: &str,
+	) -> Result<String, HttpClientError> {
+		resolve_vanilla_version(client, requested).await
+	}
+
+	fn plugin_loaders(&self) -> &'static [&'static str] {
+		&["fabric"]
+	}
+
+	fn plugin_dir(&self) -> &'static str {
+		"mods"
+	}
+}
+
+fn string_values(array: &Value) -> Vec<String> {
+	array
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(Value::as_str)
+		.map(String::from)
+		.collect()
+}
+
+// Mojang's weekly snapshots look like 24w40a.
+fn is_weekly_snapshot(id: &str) -> bool {
+	let bytes = id.as_bytes();
+
+	bytes.len() > 3 && bytes[..2].iter().all(u8::is_ascii_digit) && bytes[2] == b'w'
+}
+
+// the PaperMC-style APIs only list ids, pre-releases and snapshots are told apart by name.
+fn release_type(id: &str) -> &'static str {
+	let lower = id.to_ascii_lowercase();
+
+	if lower.contains("-pre") || lower.contains("-rc") {
+		"pre_release"
+	} else if lower.contains("snapshot") || is_weekly_snapshot(&lower) {
+		"snapshot"
+	} else {
+		"release"
+	}
+}
+
+fn first_stable(array: &Value, field: &str) -> Option<String> {
+	array
+		.as_array()?
+		.iter()
+		.find(|entry| entry["stable"].as_bool().unwrap_or(false))
+		.and_then(|entry| entry[field].as_str())
+		.map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn papermc_project(brand: ServerBrand) -> Option<&'static str> {
+		match brand.provider() {
+			Provider::PaperMc(papermc) => Some(papermc.project),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn brands_map_to_papermc_projects() {
+		assert_eq!(papermc_project(ServerBrand::Paper), Some("paper"));
+		assert_eq!(papermc_project(ServerBrand::Folia), Some("folia"));
+		assert_eq!(papermc_project(ServerBrand::Velocity), Some("velocity"));
+		assert_eq!(papermc_project(ServerBrand::Waterfall), Some("waterfall"));
+		assert_eq!(papermc_project(ServerBrand::Purpur), None);
+	}
+
+	#[test]
+	fn providers_know_where_plugins_go() {
+		assert_eq!(ServerBrand::Vanilla.plugin_loaders(), [] as [&str; 0]);
+		assert_eq!(
+			ServerBrand::Waterfall.plugin_loaders(),
+			["waterfall", "bungeecord"]
+		);
+		assert_eq!(ServerBrand::Purpur.plugin_loaders()[0], "purpur");
+		assert_eq!(ServerBrand::Paper.plugin_dir(), "plugins");
+		assert_eq!(ServerBrand::Fabric.plugin_dir(), "mods");
+		assert_eq!(ServerBrand::NeoForge.plugin_dir(), "mods");
+	}
+
+	#[test]
+	fn guesses_release_type_from_id() {
+		assert_eq!(release_type("1.21.1"), "release");
+		assert_eq!(release_type("1.21-pre1"), "pre_release");
+		assert_eq!(release_type("1.20.5-rc2"), "pre_release");
+		assert_eq!(release_type("24w40a"), "snapshot");
+		assert_eq!(release_type("3.4.0-SNAPSHOT"), "snapshot");
+	}
+
+	#[test]
+	fn estimates_java_for_listings() {
+		assert_eq!(ServerBrand::Paper.java_estimate("1.21.1"), Some(21));
+		assert_eq!(
+			ServerBrand::Velocity.java_estimate("3.4.0"),
+			Some(PROXY_JAVA_MAJOR)
+		);
+		assert_eq!(ServerBrand::Vanilla.java_estimate("24w40a"), None);
+	}
+
+	#[test]
+	fn picks_first_stable_entry() {
+		let installers = serde_json::json!([
+			{"version": "1.1.0-beta", "stable": false},
+			{"version": "1.0.1", "stable": true},
+			{"version": "1.0.0", "stable": true},
+		]);
+
+		assert_eq!(
+			first_stable(&installers, "version").as_deref(),
+			Some("1.0.1")
+		);
+		assert_eq!(first_stable(&serde_json::json!([]), "version"), None);
+	}
+
+	#[tokio::test]
+	async fn proxies_do_not_need_mojang_metadata() {
+		let major = ServerBrand::Velocity
+			.java_major(&Client::new(), "3.4.0-SNAPSHOT")
+			.await
+			.unwrap();
+
+		assert_eq!(major, PROXY_JAVA_MAJOR);
+	}
+}
@@ -0,0 +1,249 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::server_creator::HttpClientError;
+use log::warn;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::fs::{metadata, read};
+
+static CURRENT: OnceLock<Upstreams> = OnceLock::new();
+
+/// Base urls of the APIs Lunara downloads from, set under `[upstreams]` in config.toml.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Upstreams {
+	pub hangar: String,
+	pub modrinth: String,
+	pub papermc: String,
+	pub purpur: String,
+	pub fabric_meta: String,
+	pub mojang_manifest: String,
+	pub forge_maven: String,
+	pub forge_promotions: String,
+	pub neoforge_maven: String,
+	/// Offline mode. Every request is answered from this directory instead, with a url's
+	/// `<host>/<path>?<query>` as the file path, or `index.json` inside it when that is a folder.
+	pub mirror: Option<PathBuf>,
+}
+
+impl Default for Upstreams {
+	fn default() -> Self {
+		Self {
+			hangar: String::from("https://hangar.papermc.io/api/v1"),
+			modrinth: String::from("https://api.modrinth.com/v2"),
+			papermc: String::from("https://api.papermc.io/v2/projects"),
+			purpur: String::from("https://api.purpurmc.org/v2/purpur"),
+			fabric_meta: String::from("https://meta.fabricmc.net/v2/versions"),
+			mojang_manifest: String::from(
+				"https://piston-meta.mojang.com/mc/game/version_manifest_v2.json",
+			),
+			forge_maven: String::from("https://maven.minecraftforge.net/net/minecraftforge/forge"),
+			forge_promotions: String::from(
+				"https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json",
+			),
+			neoforge_maven: String::from(
+				"https://maven.neoforged.net/releases/net/neoforged/neoforge",
+			),
+			mirror: None,
+		}
+	}
+}
+
+impl Upstreams {
+	/// What `install` was given at startup, the public APIs otherwise.
+	pub fn current() -> &'static Upstreams {
+		CURRENT.get_or_init(Upstreams::default)
+	}
+
+	pub fn install(upstreams: Upstreams) {
+		if CURRENT.set(upstreams).is_err() {
+			warn!("Upstreams were already set, keeping the first ones");
+		}
+	}
+
+	/// GETs `url` as JSON. Error statuses are not rejected, callers look at the body.
+	pub async fn json<T: DeserializeOwned>(
+		&self,
+		client: &Client,
+		url: &str,
+	) -> Result<T, HttpClientError> {
+		match self.mirror_file(url).await? {
+			Some(path) => serde_json::from_slice(&read(path).await?)
+				.map_err(|_| HttpClientError::InvalidManifest("mirrored file is not JSON")),
+			None => Ok(client.get(url).send().await?.json().await?),
+		}
+	}
+
+	/// GETs `url` as text, failing on anything but a success status.
+	pub async fn text(&self, client: &Client, url: &str) -> Result<String, HttpClientError> {
+		if let Some(path) = self.mirror_file(url).await? {
+			return Ok(String::from_utf8_lossy(&read(path).await?).into_owned());
+		}
+
+		let response = client.get(url).send().await?;
+
+		if !response.status().is_success() {
+			return Err(HttpClientError::UnexpectedStatus(
+				response.status().as_u16(),
+				url.to_string(),
+			));
+		}
+
+		Ok(response.text().await?)
+	}
+
+	/// Where the mirror keeps `url`, `None` when online.
+	/// A file missing from the mirror is reported like a 404 from the real API.
+	pub async fn mirror_file(&self, url: &str) -> Result<Option<PathBuf>, HttpClientError> {
+		let Some(root) = &self.mirror else {
+			return Ok(None);
+		};
+
+		let relative: &Path = Path::new(url.split_once("://").map_or(url, |(_, rest)| rest));
+
+		// urls can come from mirrored metadata, none of them may point outside the mirror.
+		if !relative
+			.components()
+			.all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+		{
+			return Err(HttpClientError::UnexpectedStatus(404, url.to_string()));
+		}
+
+		let mut path: PathBuf = root.join(relative);
+
+		match metadata(&path).await {
+			Ok(found) if found.is_dir() => path.push("index.json"),
+			Ok(_) => {}
+			Err(e) if e.kind() == ErrorKind::NotFound => {
+				return Err(HttpClientError::UnexpectedStatus(404, url.to_string()));
+			}
+			Err(e) => return Err(e.into()),
+		}
+
+		Ok(Some(path))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::fs::{create_dir_all, remove_dir_all, write};
+
+	fn mirrored(name: &str) -> Upstreams {
+		Upstreams {
+			mirror: Some(std::env::temp_dir().join(format!(
+				"lunara-mirror-{}-{}",
+				name,
+				std::process::id()
+			))),
+			..Default::default()
+		}
+	}
+
+	#[tokio::test]
+	async fn online_has_no_mirror_files() {
+		let upstreams = Upstreams::default();
+
+		assert!(
+			upstreams
+				.mirror_file("https://api.papermc.io/v2/projects/paper")
+				.await
+				.unwrap()
+				.is_none()
+		);
+	}
+
+	#[tokio::test]
+	async fn mirror_serves_files_and_folder_indexes() {
+		let upstreams = mirrored("layout");
+		let root = upstreams.mirror.clone().unwrap();
+		let project = root.join("api.papermc.io/v2/projects/paper");
+		create_dir_all(project.join("versions")).await.unwrap();
+		write(project.join("index.json"), r#"{"versions":["1.21.1"]}"#)
+			.await
+			.unwrap();
+		write(
+			root.join("api.papermc.io/v2/projects/paper/versions/1.21.1?page=1"),
+			"ok",
+		)
+		.await
+		.unwrap();
+
+		let client = Client::new();
+		let project: serde_json::Value = upstreams
+			.json(&client, "https://api.papermc.io/v2/projects/paper")
+			.await
+			.unwrap();
+
+		assert_eq!(project["versions"][0], "1.21.1");
+		assert_eq!(
+			upstreams
+				.text(
+					&client,
+					"https://api.papermc.io/v2/projects/paper/versions/1.21.1?page=1"
+				)
+				.await
+				.unwrap(),
+			"ok"
+		);
+		assert!(matches!(
+			upstreams
+				.text(&client, "https://api.papermc.io/v2/projects/folia")
+				.await,
+			Err(HttpClientError::UnexpectedStatus(404, _))
+		));
+
+		remove_dir_all(root).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn mirror_rejects_paths_outside_its_root() {
+		let upstreams = mirrored("escape");
+		let root = upstreams.mirror.clone().unwrap();
+		create_dir_all(root.join("example.com")).await.unwrap();
+
+		for url in [
+			"file:///etc/hostname",
+			"https://example.com/../../etc/hostname",
+			"/etc/hostname",
+		] {
+			assert!(
+				matches!(
+					upstreams.mirror_file(url).await,
+					Err(HttpClientError::UnexpectedStatus(404, _))
+				),
+				"{} escaped the mirror",
+				url
+			);
+		}
+
+		remove_dir_all(root).await.unwrap();
+	}
+
+	#[test]
+	fn missing_keys_fall_back_to_defaults() {
+		let upstreams: Upstreams = toml::from_str(r#"hangar = "http://lan:8080/api/v1""#).unwrap();
+
+		assert_eq!(upstreams.hangar, "http://lan:8080/api/v1");
+		assert_eq!(upstreams.papermc, Upstreams::default().papermc);
+		assert!(upstreams.mirror.is_none());
+	}
+}
@@ -0,0 +1,182 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::server_creator::HttpClientError;
+use crate::http::server_provider::{CatalogBuild, CatalogVersion, ServerProvider};
+use crate::mc::server::ServerBrand;
+use log::debug;
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub(crate) const CATALOG_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Builds of one version, plus the Java it needs from the version's own metadata.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct VersionBuilds {
+	pub version: String,
+	pub java_major: Option<u32>,
+	pub builds: Vec<CatalogBuild>,
+}
+
+struct Cached<T> {
+	fetched: Instant,
+	value: T,
+}
+
+type CacheMap<K, V> = Arc<Mutex<HashMap<K, Cached<V>>>>;
+
+/// Version and build lists per brand, kept for a while so the UI doesn't hit every upstream API on each keystroke.
+/// Failed lookups aren't cached.
+#[derive(Clone)]
+pub struct VersionCatalog {
+	ttl: Duration,
+	versions: CacheMap<ServerBrand, Vec<CatalogVersion>>,
+	builds: CacheMap<(ServerBrand, String), VersionBuilds>,
+}
+
+impl VersionCatalog {
+	pub fn new(ttl: Duration) -> Self {
+		Self {
+			ttl,
+			versions: Arc::new(Mutex::new(HashMap::new())),
+			builds: Arc::new(Mutex::new(HashMap::new())),
+		}
+	}
+
+	pub async fn versions(
+		&self,
+		client: &Client,
+		brand: &ServerBrand,
+	) -> Result<Vec<CatalogVersion>, HttpClientError> {
+		get_or_fetch(&self.versions, brand.clone(), self.ttl, || {
+			brand.versions(client)
+		})
+		.await
+	}
+
+	pub async fn builds(
+		&self,
+		client: &Client,
+		brand: &ServerBrand,
+		version: &str,
+	) -> Result<VersionBuilds, HttpClientError> {
+		let key = (brand.clone(), version.to_string());
+
+		get_or_fetch(&self.builds, key, self.ttl, || async {
+			let builds: Vec<CatalogBuild> = brand.builds(client, version).await?;
+			let java_major: Option<u32> = brand.java_major(client, version).await.ok();
+
+			Ok(VersionBuilds {
+				version: version.to_string(),
+				java_major,
+				builds,
+			})
+		})
+		.await
+	}
+}
+
+// the lock isn't held while fetching, two callers racing on a cold entry both fetch and the last one wins.
+async fn get_or_fetch<K, V, F, Fut>(
+	cache: &Mutex<HashMap<K, Cached<V>>>,
+	key: K,
+	ttl: Duration,
+	fetch: F,
+) -> Result<V, HttpClientError>
+where
+	K: Eq + Hash,
+	V: Clone,
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = Result<V, HttpClientError>>,
+{
+	if let Some(cached) = cache.lock().await.get(&key)
+		&& cached.fetched.elapsed() < ttl
+	{
+		return Ok(cached.value.clone());
+	}
+
+	debug!("Version catalog miss, asking upstream");
+
+	let value: V = fetch().await?;
+
+	cache.lock().await.insert(
+		key,
+		Cached {
+			fetched: Instant::now(),
+			value: value.clone(),
+		},
+	);
+
+	Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	async fn fetch_counted(
+		cache: &Mutex<HashMap<&'static str, Cached<usize>>>,
+		ttl: Duration,
+		calls: &AtomicUsize,
+	) -> Result<usize, HttpClientError> {
+		get_or_fetch(cache, "paper", ttl, || async {
+			Ok(calls.fetch_add(1, Ordering::Relaxed) + 1)
+		})
+		.await
+	}
+
+	#[tokio::test]
+	async fn serves_cached_value_until_ttl_runs_out() {
+		let cache = Mutex::new(HashMap::new());
+		let calls = AtomicUsize::new(0);
+
+		assert_eq!(
+			fetch_counted(&cache, Duration::from_secs(60), &calls)
+				.await
+				.unwrap(),
+			1
+		);
+		assert_eq!(
+			fetch_counted(&cache, Duration::from_secs(60), &calls)
+				.await
+				.unwrap(),
+			1
+		);
+		assert_eq!(
+			fetch_counted(&cache, Duration::ZERO, &calls).await.unwrap(),
+			2
+		);
+	}
+
+	#[tokio::test]
+	async fn errors_are_not_cached() {
+		let cache: Mutex<HashMap<&'static str, Cached<usize>>> = Mutex::new(HashMap::new());
+
+		let failed = get_or_fetch(&cache, "paper", CATALOG_TTL, || async {
+			Err(HttpClientError::VersionNotFound(String::from("1.99")))
+		})
+		.await;
+
+		assert!(failed.is_err());
+		assert!(cache.lock().await.is_empty());
+	}
+}
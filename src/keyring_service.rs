@@ -22,6 +22,8 @@ use log::error;
 
 use tokio::task::spawn_blocking;
 
+pub const SERVICE_NAME: &str = "Lunara";
+
 pub struct KeyringService {
 	service_name: String,
 }
@@ -70,7 +72,6 @@ impl KeyringService {
 		.await?
 	}
 
-	#[allow(dead_code)]
 	pub async fn delete_secret(&self, key: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
 		let service_name: String = self.service_name.clone();
 		let key: String = key.to_string();
@@ -43,6 +43,10 @@ use crate::{
 
 use log::{LevelFilter, debug, error, info, warn};
 
+use crate::http::artifact_cache::ArtifactCache;
+use crate::http::upstream::Upstreams;
+use crate::mc::registry::{REGISTRY_LOCATION, ServerRegistry};
+use crate::mc::server::PARENT_PATH;
 use crate::route::mc_route::mc_route;
 use axum::routing::get;
 use keyring_service::KeyringService;
@@ -67,7 +71,7 @@ impl App {
 	/// Returns a result that contains database-required routes.
 	/// This function initializes a database variable and creates a pointer for it.
 	/// If the database's connection times out or something goes wrong, the functionality of returned routes won't work as intended.
-	async fn start(self) -> Result<(Router, Router), Box<dyn Error + Send + Sync>> {
+	async fn start(self) -> Result<(Router, Router, Database), Box<dyn Error + Send + Sync>> {
 		let conn_str: &String = self.config.conn_str();
 
 		info!("database connection str: {}", conn_str);
@@ -78,7 +82,7 @@ impl App {
 		let auth_route: Router<_> = auth_route::auth_api((*db).clone()).await;
 		let api_route: Router<_> = api_route::user_api((*db).clone()).await;
 
-		Ok((auth_route, api_route))
+		Ok((auth_route, api_route, (*db).clone()))
 	}
 
 	pub async fn init_keyring(
@@ -133,7 +137,13 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 
 	info!("Running Lunara.");
 
-	let keyring_service: KeyringService = KeyringService::new("Lunara");
+	if let Some(mirror) = &config.upstreams().mirror {
+		warn!("Offline mode, serving downloads from {}", mirror.display());
+	}
+
+	Upstreams::install(config.upstreams().clone());
+
+	let keyring_service: KeyringService = KeyringService::new(keyring_service::SERVICE_NAME);
 	let key: bool = keyring_service.secret_exists("key").await;
 	let first_time: bool = !key;
 
@@ -187,19 +197,31 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 		config: config.clone(),
 	};
 
-	let (db_routes, health_msg) = match app.start().await {
-		Ok((auth, api)) => {
+	let (db_routes, registry, health_msg) = match app.start().await {
+		Ok((auth, api, db)) => {
 			info!("Database connected successfully");
-			(Some((auth, api)), "Healthy!")
+
+			let registry = ServerRegistry::with_database(db);
+
+			if let Err(error) = registry.import_json(REGISTRY_LOCATION).await {
+				error!("Failed to import {}: {}", REGISTRY_LOCATION, error);
+			}
+
+			(Some((auth, api)), registry, "Healthy!")
 		}
 		Err(error) => {
 			error!("connection timed out. More information: {}", error);
 			warn!("Don't worry! You can still use Lunara without a database.");
-			(None, "Healthy (degraded mode - no database)")
+			(
+				None,
+				ServerRegistry::new(REGISTRY_LOCATION),
+				"Healthy (degraded mode - no database)",
+			)
 		}
 	};
 
-	let mc_route: Router<_> = mc_route();
+	let cache = ArtifactCache::with_settings(config.cache());
+	let mc_route: Router<_> = mc_route(registry, cache, PARENT_PATH);
 	let serve_dir = ServeDir::new("static")
 		.append_index_html_on_directories(true)
 		.not_found_service(ServeFile::new("static/index.html"));
@@ -0,0 +1,106 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+pub const SCROLLBACK_LINES: usize = 500;
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Live output of one server plus a bounded history for late joiners.
+#[derive(Clone)]
+pub struct Console {
+	scrollback: Arc<Mutex<VecDeque<String>>>,
+	capacity: usize,
+	lines: broadcast::Sender<String>,
+}
+
+impl Console {
+	pub fn new(capacity: usize) -> Self {
+		let (lines, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+		Self {
+			scrollback: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+			capacity,
+			lines,
+		}
+	}
+
+	pub fn push(&self, line: String) {
+		let Ok(mut scrollback) = self.scrollback.lock() else {
+			return;
+		};
+
+		if scrollback.len() == self.capacity {
+			scrollback.pop_front();
+		}
+
+		scrollback.push_back(line.clone());
+
+		// nobody listening is fine, the line is still in the scrollback.
+		self.lines.send(line).ok();
+	}
+
+	/// Returns the history and a receiver for everything after it.
+	/// Both are taken under the same lock so no line is missed or duplicated.
+	pub fn subscribe(&self) -> (Vec<String>, broadcast::Receiver<String>) {
+		let Ok(scrollback) = self.scrollback.lock() else {
+			return (vec![], self.lines.subscribe());
+		};
+
+		let history: Vec<String> = scrollback.iter().cloned().collect();
+		let receiver = self.lines.subscribe();
+
+		(history, receiver)
+	}
+}
+
+impl Default for Console {
+	fn default() -> Self {
+		Self::new(SCROLLBACK_LINES)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scrollback_is_bounded() {
+		let console = Console::new(3);
+
+		for i in 0..5 {
+			console.push(format!("line {}", i));
+		}
+
+		let (history, _) = console.subscribe();
+
+		assert_eq!(history, vec!["line 2", "line 3", "line 4"]);
+	}
+
+	#[tokio::test]
+	async fn subscriber_receives_new_lines() {
+		let console = Console::new(10);
+		console.push(String::from("old"));
+
+		let (history, mut receiver) = console.subscribe();
+		console.push(String::from("new"));
+
+		assert_eq!(history, vec!["old"]);
+		assert_eq!(receiver.recv().await.unwrap(), "new");
+	}
+}
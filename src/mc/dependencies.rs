@@ -0,0 +1,316 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::artifact_cache::ArtifactCache;
+use crate::http::plugin_source::{PluginDependency, PluginSource, PluginVersion};
+use crate::http::server_provider::ServerProvider;
+use crate::mc::descriptor::{InstalledJar, PluginDescriptor, scan};
+use crate::mc::plugin::{Plugin, PluginOrigin};
+use crate::mc::server::MinecraftServer;
+use log::{info, warn};
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// What installing a plugin ended up putting on the server.
+#[derive(Default, Serialize)]
+pub struct PluginInstall {
+	/// The requested plugin first, then the required dependencies it pulled in.
+	pub installed: Vec<Plugin>,
+	/// Optional dependencies that aren't installed.
+	pub optional: Vec<String>,
+	/// Required dependencies that have no release for this server or failed to download.
+	pub missing: Vec<String>,
+}
+
+/// Breadth-first walk over dependency names.
+/// Every name is queued once, so a cycle ends where it meets a name already seen.
+struct DependencyWalk {
+	present: HashSet<String>,
+	seen: HashSet<String>,
+	queue: VecDeque<String>,
+	optional: Vec<String>,
+}
+
+impl DependencyWalk {
+	fn new(root: &str, present: HashSet<String>) -> Self {
+		Self {
+			present,
+			seen: HashSet::from([key(root)]),
+			queue: VecDeque::new(),
+			optional: Vec::new(),
+		}
+	}
+
+	fn record(&mut self, name: &str) {
+		self.present.insert(key(name));
+	}
+
+	fn discover(&mut self, dependencies: impl IntoIterator<Item = PluginDependency>) {
+		for dependency in dependencies {
+			let name: String = key(&dependency.name);
+
+			if !dependency.required {
+				if !self.optional.iter().any(|known| key(known) == name) {
+					self.optional.push(dependency.name);
+				}
+			} else if !self.present.contains(&name) && self.seen.insert(name) {
+				self.queue.push_back(dependency.name);
+			}
+		}
+	}
+
+	/// Skips names an earlier install turned out to provide.
+	fn next(&mut self) -> Option<String> {
+		while let Some(name) = self.queue.pop_front() {
+			if !self.present.contains(&key(&name)) {
+				return Some(name);
+			}
+		}
+
+		None
+	}
+
+	fn missing_optional(self) -> Vec<String> {
+		let present: HashSet<String> = self.present;
+
+		self.optional
+			.into_iter()
+			.filter(|name| !present.contains(&key(name)))
+			.collect()
+	}
+}
+
+impl MinecraftServer {
+	/// Installs `plugin` and, recursively, every required dependency from the same site.
+	/// Dependencies come from the site's release metadata and the jar's own descriptor.
+	pub(crate) async fn install_plugin(
+		&self,
+		cache: &ArtifactCache,
+		plugin: Plugin,
+	) -> Result<PluginInstall, Box<dyn Error + Sync + Send>> {
+		let client: Client = Client::new();
+		let source: PluginOrigin = plugin.source();
+		let plugin_dir: PathBuf = Path::new(&self.directory()).join(self.brand().plugin_dir());
+
+		let mut walk = DependencyWalk::new(plugin.name(), installed_names(&plugin_dir).await);
+		let mut report = PluginInstall::default();
+		let mut next: Option<Plugin> = Some(plugin);
+		let mut root: bool = true;
+
+		while let Some(plugin) = next.take() {
+			// only the requested plugin failing fails the install.
+			match self.add_plugin(cache, &plugin).await {
+				Ok(mut dependencies) => {
+					walk.record(plugin.name());
+
+					if let Some(path) = self.plugin_path(plugin.name())
+						&& let Some(descriptor) = PluginDescriptor::load(path.into()).await
+					{
+						walk.record(&descriptor.name);
+						dependencies.extend(jar_dependencies(descriptor));
+					}
+
+					walk.discover(dependencies);
+					report.installed.push(plugin);
+				}
+				Err(error) if !root => {
+					warn!("Could not install dependency {}. {}", plugin.name(), error);
+					report.missing.push(plugin.name().to_string());
+				}
+				Err(error) => return Err(error),
+			}
+
+			root = false;
+
+			while let Some(name) = walk.next() {
+				match self.latest_release(&client, source, &name).await {
+					Some(version) => {
+						info!("Installing {} {}, a dependency", name, version);
+						next = Some(Plugin::new(name, version).with_source(source));
+						break;
+					}
+					None => {
+						warn!(
+							"No {:?} release of required dependency {} for {}",
+							source,
+							name,
+							self.version()
+						);
+						report.missing.push(name);
+					}
+				}
+			}
+		}
+
+		report.optional = walk.missing_optional();
+		Ok(report)
+	}
+
+	/// Newest release for this server's game version, releases for other versions don't count.
+	async fn latest_release(
+		&self,
+		client: &Client,
+		source: PluginOrigin,
+		project: &str,
+	) -> Option<String> {
+		let loaders: &[&str] = self.brand().plugin_loaders();
+
+		match source
+			.versions(client, project, loaders, Some(self.version()))
+			.await
+		{
+			Ok(versions) => versions
+				.into_iter()
+				.next()
+				.map(|PluginVersion { name, .. }| name),
+			Err(error) => {
+				warn!("Could not list releases of {}. {}", project, error);
+				None
+			}
+		}
+	}
+}
+
+fn key(name: &str) -> String {
+	name.to_lowercase()
+}
+
+fn jar_dependencies(descriptor: PluginDescriptor) -> Vec<PluginDependency> {
+	let required = descriptor.depend.into_iter().map(|name| PluginDependency {
+		name,
+		required: true,
+	});
+	let optional = descriptor
+		.soft_depend
+		.into_iter()
+		.map(|name| PluginDependency {
+			name,
+			required: false,
+		});
+
+	required.chain(optional).collect()
+}
+
+/// File names and descriptor names of the jars already in `dir`.
+async fn installed_names(dir: &Path) -> HashSet<String> {
+	let jars: Vec<InstalledJar> = scan(dir).await.unwrap_or_else(|error| {
+		warn!("Could not list {}. {}", dir.display(), error);
+		Vec::new()
+	});
+
+	jars.iter()
+		.flat_map(|jar| {
+			let named = jar
+				.descriptor
+				.as_ref()
+				.map(|descriptor| key(&descriptor.name));
+			[Some(key(jar.stem())), named]
+		})
+		.flatten()
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn required(name: &str) -> PluginDependency {
+		PluginDependency {
+			name: String::from(name),
+			required: true,
+		}
+	}
+
+	fn optional(name: &str) -> PluginDependency {
+		PluginDependency {
+			name: String::from(name),
+			required: false,
+		}
+	}
+
+	#[test]
+	fn dependency_cycles_are_walked_once() {
+		let mut walk = DependencyWalk::new("Addon", HashSet::new());
+		let mut installed: Vec<String> = Vec::new();
+
+		walk.record("Addon");
+		walk.discover([required("Core"), required("Library")]);
+
+		while let Some(name) = walk.next() {
+			walk.record(&name);
+
+			// Core and Library both depend on each other and on Addon.
+			match name.as_str() {
+				"Core" => walk.discover([required("library"), required("Addon")]),
+				"Library" => walk.discover([required("Core")]),
+				_ => {}
+			}
+
+			installed.push(name);
+		}
+
+		assert_eq!(installed, ["Core", "Library"]);
+	}
+
+	#[test]
+	fn present_plugins_are_not_reinstalled() {
+		let present: HashSet<String> = HashSet::from([key("Vault")]);
+		let mut walk = DependencyWalk::new("Shop", present);
+
+		walk.discover([required("vault"), required("Economy"), required("Economy2")]);
+		assert_eq!(walk.next().as_deref(), Some("Economy"));
+
+		// Economy's jar turns out to also be Economy2.
+		walk.record("Economy");
+		walk.record("Economy2");
+		assert_eq!(walk.next(), None);
+	}
+
+	#[test]
+	fn only_uninstalled_optional_dependencies_are_reported() {
+		let mut walk = DependencyWalk::new("Addon", HashSet::new());
+
+		walk.discover([
+			optional("PlaceholderAPI"),
+			optional("Vault"),
+			optional("vault"),
+		]);
+		walk.discover([required("Vault")]);
+
+		while let Some(name) = walk.next() {
+			walk.record(&name);
+		}
+
+		assert_eq!(walk.missing_optional(), ["PlaceholderAPI"]);
+	}
+
+	#[test]
+	fn jar_dependencies_keep_required_and_optional_apart() {
+		let descriptor = PluginDescriptor {
+			depend: vec![String::from("Essentials")],
+			soft_depend: vec![String::from("Vault")],
+			..PluginDescriptor::default()
+		};
+
+		assert_eq!(
+			jar_dependencies(descriptor),
+			[required("Essentials"), optional("Vault")]
+		);
+	}
+}
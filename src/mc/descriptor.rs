@@ -0,0 +1,374 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::mc::plugin::PluginOrigin;
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml_ng::Value;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::fs::read_dir;
+use tokio::task::spawn_blocking;
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+// Paper loads paper-plugin.yml over plugin.yml when a jar has both.
+const DESCRIPTOR_FILES: [&str; 2] = ["paper-plugin.yml", "plugin.yml"];
+
+#[derive(Debug, Error)]
+pub enum DescriptorError {
+	#[error("File error: {0}")]
+	Io(#[from] std::io::Error),
+	#[error("Not a jar: {0}")]
+	Zip(#[from] ZipError),
+	#[error("Invalid descriptor: {0}")]
+	Yaml(#[from] serde_yaml_ng::Error),
+}
+
+/// What a plugin jar says about itself.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PluginDescriptor {
+	pub name: String,
+	pub version: String,
+	pub main: String,
+	pub api_version: Option<String>,
+	pub authors: Vec<String>,
+	/// Plugins that have to be installed for this one to load.
+	pub depend: Vec<String>,
+	pub soft_depend: Vec<String>,
+}
+
+/// A jar in a server's plugin folder, installed through Lunara or dropped in by hand.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct InstalledJar {
+	pub file: String,
+	/// `None` for jars without a plugin.yml or paper-plugin.yml.
+	pub descriptor: Option<PluginDescriptor>,
+	/// Where Lunara installed it from, `None` for jars it didn't install.
+	pub source: Option<PluginOrigin>,
+}
+
+impl InstalledJar {
+	/// The name the jar was saved under, which is the project's for Lunara's installs.
+	pub fn stem(&self) -> &str {
+		self.file.strip_suffix(".jar").unwrap_or(&self.file)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawDescriptor {
+	name: String,
+	#[serde(default)]
+	version: Value,
+	#[serde(default)]
+	main: String,
+	#[serde(default)]
+	api_version: Value,
+	author: Option<String>,
+	#[serde(default, deserialize_with = "string_list")]
+	authors: Vec<String>,
+	#[serde(default, deserialize_with = "string_list")]
+	depend: Vec<String>,
+	#[serde(default, deserialize_with = "string_list")]
+	softdepend: Vec<String>,
+	// paper-plugin.yml only.
+	#[serde(default)]
+	dependencies: Value,
+}
+
+impl PluginDescriptor {
+	/// `None` when the jar has no Bukkit or Paper descriptor, mods for example.
+	pub(crate) fn read_jar(path: &Path) -> Result<Option<Self>, DescriptorError> {
+		let mut archive = ZipArchive::new(File::open(path)?)?;
+
+		for file in DESCRIPTOR_FILES {
+			let mut content = String::new();
+
+			match archive.by_name(file) {
+				Ok(mut entry) => entry.read_to_string(&mut content)?,
+				Err(ZipError::FileNotFound) => continue,
+				Err(error) => return Err(error.into()),
+			};
+
+			return Ok(Some(Self::parse(&content)?));
+		}
+
+		Ok(None)
+	}
+
+	/// `read_jar` off the async runtime, logging jars that can't be read.
+	pub(crate) async fn load(path: PathBuf) -> Option<Self> {
+		let file: String = path.display().to_string();
+
+		match spawn_blocking(move || Self::read_jar(&path)).await {
+			Ok(Ok(descriptor)) => descriptor,
+			Ok(Err(error)) => {
+				warn!("Unreadable plugin jar {}. {}", file, error);
+				None
+			}
+			Err(_) => None,
+		}
+	}
+
+	pub(crate) fn parse(content: &str) -> Result<Self, DescriptorError> {
+		let raw: RawDescriptor = serde_yaml_ng::from_str(content)?;
+
+		let mut depend: Vec<String> = raw.depend;
+		let mut soft_depend: Vec<String> = raw.softdepend;
+
+		for (name, required) in paper_dependencies(&raw.dependencies) {
+			let list = if required {
+				&mut depend
+			} else {
+				&mut soft_depend
+			};
+
+			if !list.contains(&name) {
+				list.push(name);
+			}
+		}
+
+		let mut authors: Vec<String> = raw.author.into_iter().collect();
+
+		for author in raw.authors {
+			if !authors.contains(&author) {
+				authors.push(author);
+			}
+		}
+
+		Ok(Self {
+			name: raw.name,
+			version: scalar(&raw.version).unwrap_or_default(),
+			main: raw.main,
+			api_version: scalar(&raw.api_version),
+			authors,
+			depend,
+			soft_depend,
+		})
+	}
+}
+
+/// Every jar in `dir` sorted by file name, a folder that doesn't exist yet has none.
+pub(crate) async fn scan(dir: &Path) -> std::io::Result<Vec<InstalledJar>> {
+	let mut entries = match read_dir(dir).await {
+		Ok(entries) => entries,
+		Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(error) => return Err(error),
+	};
+
+	let mut jars: Vec<InstalledJar> = Vec::new();
+
+	while let Some(entry) = entries.next_entry().await? {
+		let path: PathBuf = entry.path();
+
+		if !entry.file_type().await?.is_file()
+			|| path.extension().is_none_or(|extension| extension != "jar")
+		{
+			continue;
+		}
+
+		jars.push(InstalledJar {
+			file: entry.file_name().to_string_lossy().into_owned(),
+			descriptor: PluginDescriptor::load(path).await,
+			source: None,
+		});
+	}
+
+	jars.sort_by(|a, b| a.file.cmp(&b.file));
+	Ok(jars)
+}
+
+// `softdepend:` with nothing after it is null, and a lone name is accepted as well.
+fn string_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+	Ok(match Value::deserialize(deserializer)? {
+		Value::Sequence(values) => values.iter().filter_map(scalar).collect(),
+		value => scalar(&value).into_iter().collect(),
+	})
+}
+
+// `version: 1.0` is a number to YAML.
+fn scalar(value: &Value) -> Option<String> {
+	match value {
+		Value::String(s) => Some(s.clone()),
+		Value::Number(n) => Some(n.to_string()),
+		Value::Bool(b) => Some(b.to_string()),
+		_ => None,
+	}
+}
+
+/// Both layouts Paper has used, `server:` entries keyed by name and the older list.
+fn paper_dependencies(dependencies: &Value) -> Vec<(String, bool)> {
+	let required = |entry: &Value| entry["required"].as_bool().unwrap_or(true);
+
+	match dependencies {
+		Value::Mapping(_) => dependencies["server"]
+			.as_mapping()
+			.into_iter()
+			.flatten()
+			.filter_map(|(name, entry)| Some((name.as_str()?.to_string(), required(entry))))
+			.collect(),
+		Value::Sequence(entries) => entries
+			.iter()
+			.filter(|entry| !entry["bootstrap"].as_bool().unwrap_or(false))
+			.filter_map(|entry| Some((entry["name"].as_str()?.to_string(), required(entry))))
+			.collect(),
+		_ => Vec::new(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+	use zip::ZipWriter;
+	use zip::write::SimpleFileOptions;
+
+	#[test]
+	fn plugin_yml_is_parsed() {
+		let descriptor = PluginDescriptor::parse(
+			"name: EssentialsChat\n\
+			 version: 2.21\n\
+			 main: com.earth2me.essentials.chat.EssentialsChat\n\
+			 api-version: '1.13'\n\
+			 author: zenexer\n\
+			 authors: [ementalo, snowleo]\n\
+			 depend: [Essentials]\n\
+			 softdepend: [Vault]\n",
+		)
+		.unwrap();
+
+		assert_eq!(descriptor.name, "EssentialsChat");
+		assert_eq!(descriptor.version, "2.21");
+		assert_eq!(descriptor.api_version.as_deref(), Some("1.13"));
+		assert_eq!(descriptor.authors, ["zenexer", "ementalo", "snowleo"]);
+		assert_eq!(descriptor.depend, ["Essentials"]);
+		assert_eq!(descriptor.soft_depend, ["Vault"]);
+	}
+
+	#[test]
+	fn empty_lists_and_repeated_authors_are_tolerated() {
+		let descriptor = PluginDescriptor::parse(
+			"name: Tool\n\
+			 author: sk89q\n\
+			 authors: [me4502, sk89q]\n\
+			 depend: WorldEdit\n\
+			 softdepend:\n",
+		)
+		.unwrap();
+
+		assert_eq!(descriptor.authors, ["sk89q", "me4502"]);
+		assert_eq!(descriptor.depend, ["WorldEdit"]);
+		assert!(descriptor.soft_depend.is_empty());
+	}
+
+	#[test]
+	fn paper_plugin_yml_dependencies_are_split_by_required() {
+		let current = PluginDescriptor::parse(
+			"name: Addon\n\
+			 version: '1.0'\n\
+			 main: dev.addon.Addon\n\
+			 dependencies:\n  \
+			   server:\n    \
+			     LuckPerms:\n      \
+			       load: BEFORE\n    \
+			     PlaceholderAPI:\n      \
+			       required: false\n",
+		)
+		.unwrap();
+
+		assert_eq!(current.depend, ["LuckPerms"]);
+		assert_eq!(current.soft_depend, ["PlaceholderAPI"]);
+
+		let legacy = PluginDescriptor::parse(
+			"name: Addon\n\
+			 dependencies:\n  \
+			   - name: LuckPerms\n  \
+			   - name: Loader\n    \
+			     bootstrap: true\n  \
+			   - name: Vault\n    \
+			     required: false\n",
+		)
+		.unwrap();
+
+		assert_eq!(legacy.depend, ["LuckPerms"]);
+		assert_eq!(legacy.soft_depend, ["Vault"]);
+		assert!(legacy.main.is_empty());
+	}
+
+	fn write_jar(path: &Path, files: &[(&str, &str)]) {
+		let mut jar = ZipWriter::new(File::create(path).unwrap());
+
+		for (name, content) in files {
+			jar.start_file(*name, SimpleFileOptions::default()).unwrap();
+			jar.write_all(content.as_bytes()).unwrap();
+		}
+
+		jar.finish().unwrap();
+	}
+
+	#[test]
+	fn jars_prefer_paper_plugin_yml() {
+		let path =
+			std::env::temp_dir().join(format!("lunara-descriptor-{}.jar", std::process::id()));
+
+		write_jar(
+			&path,
+			&[
+				("plugin.yml", "name: Bukkit\n"),
+				("paper-plugin.yml", "name: Paper\n"),
+			],
+		);
+
+		let descriptor = PluginDescriptor::read_jar(&path).unwrap().unwrap();
+		std::fs::remove_file(&path).unwrap();
+
+		assert_eq!(descriptor.name, "Paper");
+	}
+
+	#[tokio::test]
+	async fn scan_lists_every_jar_including_unreadable_ones() {
+		let dir = std::env::temp_dir().join(format!("lunara-plugins-{}", std::process::id()));
+		std::fs::create_dir_all(dir.join("LuckPerms")).unwrap();
+
+		write_jar(
+			&dir.join("manual.jar"),
+			&[(
+				"plugin.yml",
+				"name: Manual\nversion: 1.2\nmain: dev.Manual\n",
+			)],
+		);
+		write_jar(&dir.join("fabric-api.jar"), &[("fabric.mod.json", "{}")]);
+		std::fs::write(dir.join("broken.jar"), b"not a zip").unwrap();
+		std::fs::write(dir.join("config.yml"), b"key: value").unwrap();
+
+		let jars = scan(&dir).await.unwrap();
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		let files: Vec<&str> = jars.iter().map(|jar| jar.file.as_str()).collect();
+		assert_eq!(files, ["broken.jar", "fabric-api.jar", "manual.jar"]);
+
+		let manual = jars[2].descriptor.as_ref().unwrap();
+		assert_eq!(manual.name, "Manual");
+		assert_eq!(manual.version, "1.2");
+		assert_eq!(jars[2].stem(), "manual");
+		assert!(jars[0].descriptor.is_none() && jars[1].descriptor.is_none());
+
+		assert!(scan(&dir).await.unwrap().is_empty());
+	}
+}
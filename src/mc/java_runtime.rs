@@ -0,0 +1,239 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use log::{debug, warn};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::fs::{read_dir, read_to_string};
+
+pub(crate) const DEFAULT_JAVA: &str = "java";
+
+// where distro packages and the usual tarball installs end up.
+const SEARCH_DIRS: [&str; 4] = ["/usr/lib/jvm", "/usr/java", "/opt/java", "/opt/jdk"];
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct JavaRuntime {
+	pub home: PathBuf,
+	pub version: String,
+	pub major: u32,
+}
+
+impl JavaRuntime {
+	pub fn executable(&self) -> PathBuf {
+		self.home.join("bin").join("java")
+	}
+}
+
+/// Installed JDKs and JREs, found by their `release` file.
+#[derive(Debug, Clone, Default)]
+pub struct JavaRegistry {
+	runtimes: Vec<JavaRuntime>,
+}
+
+impl JavaRegistry {
+	pub async fn discover() -> Self {
+		let mut dirs: Vec<PathBuf> = SEARCH_DIRS.iter().map(PathBuf::from).collect();
+
+		if let Ok(java_home) = std::env::var("JAVA_HOME") {
+			dirs.push(PathBuf::from(java_home));
+		}
+
+		Self::discover_in(&dirs).await
+	}
+
+	/// Each dir is either a runtime itself or a parent holding several of them.
+	pub async fn discover_in(dirs: &[PathBuf]) -> Self {
+		let mut runtimes: Vec<JavaRuntime> = vec![];
+
+		for dir in dirs {
+			if let Some(runtime) = read_runtime(dir).await {
+				runtimes.push(runtime);
+				continue;
+			}
+
+			let Ok(mut entries) = read_dir(dir).await else {
+				continue;
+			};
+
+			while let Ok(Some(entry)) = entries.next_entry().await {
+				if let Some(runtime) = read_runtime(&entry.path()).await {
+					runtimes.push(runtime);
+				}
+			}
+		}
+
+		runtimes.sort_by(|a, b| a.major.cmp(&b.major).then(a.home.cmp(&b.home)));
+		runtimes.dedup_by(|a, b| a.home == b.home);
+
+		debug!("Discovered {} java runtimes", runtimes.len());
+
+		Self { runtimes }
+	}
+
+	pub fn runtimes(&self) -> &[JavaRuntime] {
+		&self.runtimes
+	}
+
+	/// Whether the path is the java binary of one of the discovered runtimes.
+	pub fn is_known_executable(&self, path: &Path) -> bool {
+		self.runtimes
+			.iter()
+			.any(|runtime| runtime.executable() == path)
+	}
+
+	/// Prefers the exact major, otherwise the oldest newer one since Java is mostly
+	/// backwards compatible but old servers tend to break on much newer runtimes.
+	pub fn select(&self, required_major: u32) -> Option<&JavaRuntime> {
+		self.runtimes
+			.iter()
+			.find(|runtime| runtime.major == required_major)
+			.or_else(|| {
+				self.runtimes
+					.iter()
+					.find(|runtime| runtime.major > required_major)
+			})
+	}
+
+	/// Path of the java binary to launch, or plain `java` from PATH when nothing fits.
+	pub fn executable_for(&self, required_major: u32) -> String {
+		match self.select(required_major) {
+			Some(runtime) => runtime.executable().to_string_lossy().into_owned(),
+			None => {
+				warn!(
+					"No Java {} runtime installed, falling back to {}",
+					required_major, DEFAULT_JAVA
+				);
+				String::from(DEFAULT_JAVA)
+			}
+		}
+	}
+}
+
+async fn read_runtime(home: &Path) -> Option<JavaRuntime> {
+	let release = read_to_string(home.join("release")).await.ok()?;
+	let version = parse_release(&release)?;
+	let major = java_major(&version)?;
+
+	Some(JavaRuntime {
+		home: home.to_path_buf(),
+		version,
+		major,
+	})
+}
+
+/// Pulls `JAVA_VERSION` out of a JDK `release` file.
+fn parse_release(content: &str) -> Option<String> {
+	content.lines().find_map(|line| {
+		let value = line.trim().strip_prefix("JAVA_VERSION=")?;
+		Some(value.trim_matches('"').to_string())
+	})
+}
+
+/// "1.8.0_392" is Java 8, "17.0.9" is Java 17.
+fn java_major(version: &str) -> Option<u32> {
+	let mut parts = version.split(['.', '_', '-', '+']);
+	let first: u32 = parts.next()?.parse().ok()?;
+
+	if first == 1 {
+		return parts.next()?.parse().ok();
+	}
+
+	Some(first)
+}
+
+/// Best guess for versions whose Mojang metadata has no `javaVersion`,
+/// or when it couldn't be fetched.
+pub fn fallback_major(mc_version: &str) -> u32 {
+	let mut parts = mc_version
+		.split(['.', '-'])
+		.map(|part| part.parse::<u32>().unwrap_or(0));
+
+	let minor = parts.nth(1).unwrap_or(0);
+	let patch = parts.next().unwrap_or(0);
+
+	match (minor, patch) {
+		(21.., _) | (20, 5..) => 21,
+		(18.., _) => 17,
+		(17, _) => 16,
+		_ => 8,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs::{create_dir_all, write};
+
+	#[test]
+	fn parses_release_file() {
+		let release = "IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"17.0.9\"\n";
+
+		assert_eq!(parse_release(release).as_deref(), Some("17.0.9"));
+		assert_eq!(parse_release("OS_NAME=\"Linux\""), None);
+	}
+
+	#[test]
+	fn reads_major_from_legacy_and_modern_versions() {
+		assert_eq!(java_major("1.8.0_392"), Some(8));
+		assert_eq!(java_major("17.0.9"), Some(17));
+		assert_eq!(java_major("21"), Some(21));
+		assert_eq!(java_major("garbage"), None);
+	}
+
+	#[test]
+	fn guesses_java_for_minecraft_versions() {
+		assert_eq!(fallback_major("1.12.2"), 8);
+		assert_eq!(fallback_major("1.17.1"), 16);
+		assert_eq!(fallback_major("1.20.4"), 17);
+		assert_eq!(fallback_major("1.20.5"), 21);
+		assert_eq!(fallback_major("1.21.1"), 21);
+	}
+
+	#[tokio::test]
+	async fn discovers_and_selects_runtimes() {
+		let root = std::env::temp_dir().join(format!("lunara-jvm-{}", std::process::id()));
+
+		for (name, version) in [
+			("jdk8", "1.8.0_392"),
+			("jdk17", "17.0.9"),
+			("jdk21", "21.0.2"),
+		] {
+			create_dir_all(root.join(name)).unwrap();
+			write(
+				root.join(name).join("release"),
+				format!("JAVA_VERSION=\"{}\"\n", version),
+			)
+			.unwrap();
+		}
+		create_dir_all(root.join("not-a-jdk")).unwrap();
+
+		let registry = JavaRegistry::discover_in(std::slice::from_ref(&root)).await;
+
+		assert_eq!(registry.runtimes().len(), 3);
+		assert_eq!(registry.select(17).unwrap().home, root.join("jdk17"));
+		assert_eq!(registry.select(16).unwrap().major, 17);
+		assert_eq!(registry.select(25), None);
+		assert_eq!(registry.executable_for(25), DEFAULT_JAVA);
+		assert!(registry.is_known_executable(&root.join("jdk17").join("bin").join("java")));
+		assert!(!registry.is_known_executable(Path::new("/bin/sh")));
+		assert_eq!(
+			registry.executable_for(8),
+			root.join("jdk8").join("bin").join("java").to_string_lossy()
+		);
+
+		std::fs::remove_dir_all(root).unwrap();
+	}
+}
@@ -0,0 +1,225 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::download::DownloadProgress;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, watch};
+use uuid::Uuid;
+
+// finished jobs stay around this long so clients polling slowly still see the outcome.
+const FINISHED_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobState {
+	Running,
+	Done,
+	Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+	pub id: Uuid,
+	pub description: String,
+	#[serde(flatten)]
+	pub state: JobState,
+	pub progress: DownloadProgress,
+}
+
+struct Job {
+	description: String,
+	state: JobState,
+	progress: watch::Receiver<DownloadProgress>,
+	finished_at: Option<Instant>,
+}
+
+impl Job {
+	fn status(&self, id: Uuid) -> JobStatus {
+		JobStatus {
+			id,
+			description: self.description.clone(),
+			state: self.state.clone(),
+			progress: *self.progress.borrow(),
+		}
+	}
+}
+
+/// Long running work (downloads mostly) that the API hands back as an id to poll.
+#[derive(Clone, Default)]
+pub struct Jobs {
+	jobs: Arc<Mutex<HashMap<Uuid, Job>>>,
+}
+
+impl Jobs {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Runs `work` in the background. It gets a sender to publish download progress on.
+	pub async fn spawn<F, Fut>(&self, description: String, work: F) -> JobStatus
+	where
+		F: FnOnce(watch::Sender<DownloadProgress>) -> Fut,
+		Fut: Future<Output = Result<(), String>> + Send + 'static,
+	{
+		let id: Uuid = Uuid::new_v4();
+		let (progress_tx, progress_rx) = watch::channel(DownloadProgress::default());
+
+		let job = Job {
+			description,
+			state: JobState::Running,
+			progress: progress_rx,
+			finished_at: None,
+		};
+		let status: JobStatus = job.status(id);
+
+		{
+			let mut jobs = self.jobs.lock().await;
+
+			jobs.retain(|_, job| {
+				job.finished_at
+					.is_none_or(|finished| finished.elapsed() < FINISHED_RETENTION)
+			});
+			jobs.insert(id, job);
+		}
+
+		let future = work(progress_tx);
+		let jobs = self.jobs.clone();
+
+		tokio::spawn(async move {
+			// the work runs in its own task so a panic surfaces here as a JoinError.
+			let state = match tokio::spawn(future).await {
+				Ok(Ok(())) => JobState::Done,
+				Ok(Err(error)) => JobState::Failed { error },
+				Err(error) => JobState::Failed {
+					error: format!("job panicked: {}", error),
+				},
+			};
+
+			if let Some(job) = jobs.lock().await.get_mut(&id) {
+				job.state = state;
+				job.finished_at = Some(Instant::now());
+			}
+		});
+
+		status
+	}
+
+	pub async fn status(&self, id: Uuid) -> Option<JobStatus> {
+		self.jobs.lock().await.get(&id).map(|job| job.status(id))
+	}
+
+	pub async fn list(&self) -> Vec<JobStatus> {
+		self.jobs
+			.lock()
+			.await
+			.iter()
+			.map(|(id, job)| job.status(*id))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	async fn wait_for_finish(jobs: &Jobs, id: Uuid) -> JobStatus {
+		loop {
+			let status = jobs.status(id).await.unwrap();
+
+			if status.state != JobState::Running {
+				return status;
+			}
+
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	}
+
+	#[tokio::test]
+	async fn job_reports_progress_and_completion() {
+		let jobs = Jobs::new();
+
+		let started = jobs
+			.spawn(String::from("download"), |progress| async move {
+				progress.send_replace(DownloadProgress {
+					downloaded: 5,
+					total: Some(10),
+				});
+				Ok(())
+			})
+			.await;
+
+		assert_eq!(started.state, JobState::Running);
+
+		let finished = wait_for_finish(&jobs, started.id).await;
+
+		assert_eq!(finished.state, JobState::Done);
+		assert_eq!(finished.progress.downloaded, 5);
+		assert_eq!(jobs.list().await.len(), 1);
+	}
+
+	#[tokio::test]
+	async fn failed_job_keeps_error() {
+		let jobs = Jobs::new();
+
+		let started = jobs
+			.spawn(String::from("download"), |_| async move {
+				Err(String::from("checksum mismatch"))
+			})
+			.await;
+
+		let finished = wait_for_finish(&jobs, started.id).await;
+
+		assert_eq!(
+			finished.state,
+			JobState::Failed {
+				error: String::from("checksum mismatch")
+			}
+		);
+	}
+
+	#[tokio::test]
+	async fn panicking_job_fails() {
+		let jobs = Jobs::new();
+
+		let started = jobs
+			.spawn(String::from("install"), |_| async move {
+				panic!("installer blew up");
+			})
+			.await;
+
+		let finished = wait_for_finish(&jobs, started.id).await;
+
+		assert!(matches!(finished.state, JobState::Failed { .. }));
+	}
+
+	#[test]
+	fn status_serializes_flat() {
+		let status = JobStatus {
+			id: Uuid::nil(),
+			description: String::from("x"),
+			state: JobState::Running,
+			progress: DownloadProgress::default(),
+		};
+
+		let json = serde_json::to_value(&status).unwrap();
+
+		assert_eq!(json["state"], "running");
+		assert_eq!(json["progress"]["downloaded"], 0);
+	}
+}
@@ -0,0 +1,375 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::mc::java_runtime::JavaRegistry;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const DEFAULT_MAX_HEAP: &str = "2G";
+
+// -XX options that run commands, load code or read more options from a file.
+const FORBIDDEN_XX: [&str; 4] = [
+	"-XX:On",
+	"-XX:+UnlockDiagnosticVMOptions",
+	"-XX:Flags=",
+	"-XX:VMOptionsFile=",
+];
+
+// the heap is set through min_heap and max_heap, where it is checked.
+const HEAP_FLAGS: [&str; 4] = ["-Xms", "-Xmx", "-XX:InitialHeapSize=", "-XX:MaxHeapSize="];
+
+// Paper options that load plugin jars from outside the server's plugin folder.
+const FORBIDDEN_PROGRAM_ARGS: [&str; 3] = ["--add-plugin", "--add-extra-plugin-jar", "--plugins"];
+
+// https://docs.papermc.io/paper/aikars-flags
+const AIKAR_FLAGS: [&str; 20] = [
+	"-XX:+UseG1GC",
+	"-XX:+ParallelRefProcEnabled",
+	"-XX:MaxGCPauseMillis=200",
+	"-XX:+UnlockExperimentalVMOptions",
+	"-XX:+DisableExplicitGC",
+	"-XX:+AlwaysPreTouch",
+	"-XX:G1NewSizePercent=30",
+	"-XX:G1MaxNewSizePercent=40",
+	"-XX:G1HeapRegionSize=8M",
+	"-XX:G1ReservePercent=20",
+	"-XX:G1HeapWastePercent=5",
+	"-XX:G1MixedGCCountTarget=4",
+	"-XX:InitiatingHeapOccupancyPercent=15",
+	"-XX:G1MixedGCLiveThresholdPercent=90",
+	"-XX:G1RSetUpdatingPauseIntervalPercent=5",
+	"-XX:SurvivorRatio=32",
+	"-XX:+PerfDisableSharedMem",
+	"-XX:MaxTenuringThreshold=1",
+	"-Dusing.aikars.flags=https://mcflags.emc.gs",
+	"-Daikars.new.flags=true",
+];
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagPreset {
+	#[default]
+	None,
+	Aikar,
+}
+
+impl FlagPreset {
+	pub fn flags(&self) -> &'static [&'static str] {
+		match self {
+			FlagPreset::None => &[],
+			FlagPreset::Aikar => &AIKAR_FLAGS,
+		}
+	}
+}
+
+/// What the JVM runs, relative to the server directory. Modern Forge and NeoForge
+/// installers leave an args file instead of a runnable jar.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", content = "path", rename_all = "snake_case")]
+pub enum LaunchTarget {
+	Jar(String),
+	ArgsFile(String),
+}
+
+impl LaunchTarget {
+	fn arguments(&self) -> Vec<String> {
+		match self {
+			LaunchTarget::Jar(jar) => vec![String::from("-jar"), jar.clone()],
+			LaunchTarget::ArgsFile(file) => vec![format!("@{}", file)],
+		}
+	}
+}
+
+/// How the JVM for a server is launched.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct LaunchProfile {
+	pub java_path: Option<String>,
+	pub min_heap: Option<String>,
+	pub max_heap: Option<String>,
+	pub preset: FlagPreset,
+	pub jvm_args: Vec<String>,
+	pub program_args: Vec<String>,
+	pub nogui: bool,
+}
+
+impl Default for LaunchProfile {
+	fn default() -> Self {
+		Self {
+			java_path: None,
+			min_heap: None,
+			max_heap: Some(String::from(DEFAULT_MAX_HEAP)),
+			preset: FlagPreset::None,
+			jvm_args: vec![],
+			program_args: vec![],
+			nogui: true,
+		}
+	}
+}
+
+impl LaunchProfile {
+	/// Builds the full argument list, jvm flags first and program args after the target.
+	pub fn arguments_for(&self, target: &LaunchTarget) -> Vec<String> {
+		let mut args: Vec<String> = vec![];
+
+		if let Some(min_heap) = &self.min_heap {
+			args.push(format!("-Xms{}", min_heap));
+		}
+
+		if let Some(max_heap) = &self.max_heap {
+			args.push(format!("-Xmx{}", max_heap));
+		}
+
+		args.extend(self.preset.flags().iter().map(|flag| flag.to_string()));
+		args.extend(self.jvm_args.iter().cloned());
+		args.extend(target.arguments());
+
+		if self.nogui {
+			args.push(String::from("nogui"));
+		}
+
+		args.extend(self.program_args.iter().cloned());
+		args
+	}
+
+	/// Profiles come from the API, so nothing in them may pick what code runs: the java path
+	/// has to be an installed runtime, jvm args are limited to `-D` properties and `-XX`
+	/// tuning flags, and program args can't add plugin jars.
+	pub fn validate(&self, java: &JavaRegistry) -> Result<(), String> {
+		for heap in [&self.min_heap, &self.max_heap].into_iter().flatten() {
+			if !is_heap_size(heap) {
+				return Err(format!(
+					"invalid heap size '{}', use something like 512M or 4G",
+					heap
+				));
+			}
+		}
+
+		if let (Some(min), Some(max)) = (&self.min_heap, &self.max_heap)
+			&& heap_bytes(min) > heap_bytes(max)
+		{
+			return Err(format!("min heap {} is larger than max heap {}", min, max));
+		}
+
+		if let Some(path) = &self.java_path
+			&& !java.is_known_executable(Path::new(path))
+		{
+			return Err(format!(
+				"java path '{}' is not the java binary of an installed runtime",
+				path
+			));
+		}
+
+		if let Some(arg) = self.jvm_args.iter().find(|arg| !is_allowed_jvm_arg(arg)) {
+			return Err(format!(
+				"jvm arg '{}' is not allowed, use -D properties, -XX tuning flags and the heap fields",
+				arg
+			));
+		}
+
+		if let Some(arg) = self.program_args.iter().find(|arg| {
+			FORBIDDEN_PROGRAM_ARGS
+				.iter()
+				.any(|forbidden| arg.starts_with(forbidden))
+		}) {
+			return Err(format!("program arg '{}' is not allowed", arg));
+		}
+
+		Ok(())
+	}
+}
+
+fn is_allowed_jvm_arg(arg: &str) -> bool {
+	if HEAP_FLAGS.iter().any(|flag| arg.starts_with(flag)) {
+		return false;
+	}
+
+	if arg.starts_with("-XX:") {
+		return !FORBIDDEN_XX.iter().any(|flag| arg.starts_with(flag));
+	}
+
+	arg.starts_with("-D")
+}
+
+fn is_heap_size(value: &str) -> bool {
+	let digits = value.trim_end_matches(['k', 'K', 'm', 'M', 'g', 'G']);
+
+	!digits.is_empty()
+		&& value.len() - digits.len() <= 1
+		&& digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn heap_bytes(value: &str) -> u64 {
+	let digits = value.trim_end_matches(char::is_alphabetic);
+	let amount: u64 = digits.parse().unwrap_or_default();
+
+	let multiplier: u64 = match value[digits.len()..].to_ascii_lowercase().as_str() {
+		"k" => 1 << 10,
+		"m" => 1 << 20,
+		"g" => 1 << 30,
+		_ => 1,
+	};
+
+	amount.saturating_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn default_profile_caps_heap_and_disables_gui() {
+		let profile = LaunchProfile::default();
+
+		assert_eq!(profile.java_path, None);
+		assert_eq!(
+			profile.arguments_for(&LaunchTarget::Jar(String::from("server.jar"))),
+			vec!["-Xmx2G", "-jar", "server.jar", "nogui"]
+		);
+	}
+
+	#[test]
+	fn arguments_are_ordered() {
+		let profile = LaunchProfile {
+			java_path: Some(String::from("/usr/lib/jvm/java-21/bin/java")),
+			min_heap: Some(String::from("1G")),
+			max_heap: Some(String::from("4G")),
+			preset: FlagPreset::Aikar,
+			jvm_args: vec![String::from("-Dfile.encoding=UTF-8")],
+			program_args: vec![String::from("--port"), String::from("25566")],
+			nogui: true,
+		};
+
+		let args = profile.arguments_for(&LaunchTarget::Jar(String::from("paper.jar")));
+
+		assert_eq!(&args[..3], ["-Xms1G", "-Xmx4G", "-XX:+UseG1GC"]);
+		assert_eq!(
+			&args[args.len() - 6..],
+			[
+				"-Dfile.encoding=UTF-8",
+				"-jar",
+				"paper.jar",
+				"nogui",
+				"--port",
+				"25566"
+			]
+		);
+		assert_eq!(args.len(), 2 + AIKAR_FLAGS.len() + 1 + 3 + 2);
+	}
+
+	#[test]
+	fn args_file_replaces_jar() {
+		let profile = LaunchProfile::default();
+		let target = LaunchTarget::ArgsFile(String::from(
+			"libraries/net/minecraftforge/forge/1.20.1-47.3.0/unix_args.txt",
+		));
+
+		assert_eq!(
+			profile.arguments_for(&target),
+			vec![
+				"-Xmx2G",
+				"@libraries/net/minecraftforge/forge/1.20.1-47.3.0/unix_args.txt",
+				"nogui"
+			]
+		);
+	}
+
+	#[test]
+	fn validates_heap_sizes() {
+		let java = JavaRegistry::default();
+		let mut profile = LaunchProfile::default();
+		assert!(profile.validate(&java).is_ok());
+
+		profile.max_heap = Some(String::from("lots"));
+		assert!(profile.validate(&java).is_err());
+
+		profile.max_heap = Some(String::from("1G"));
+		profile.min_heap = Some(String::from("2048M"));
+		assert!(profile.validate(&java).is_err());
+
+		profile.min_heap = Some(String::from("512m"));
+		assert!(profile.validate(&java).is_ok());
+	}
+
+	#[test]
+	fn rejects_jvm_args_that_run_code_or_override_the_heap() {
+		let java = JavaRegistry::default();
+		let mut profile = LaunchProfile::default();
+
+		for arg in [
+			"-XX:OnOutOfMemoryError=sh -c id",
+			"-XX:OnError=id",
+			"-XX:+UnlockDiagnosticVMOptions",
+			"-XX:VMOptionsFile=/tmp/opts",
+			"-javaagent:/tmp/agent.jar",
+			"-agentpath:/tmp/agent.so",
+			"-Xmx64G",
+			"-XX:MaxHeapSize=64G",
+			"@/tmp/args.txt",
+			"-cp",
+		] {
+			profile.jvm_args = vec![String::from(arg)];
+			assert!(profile.validate(&java).is_err(), "{} was accepted", arg);
+		}
+
+		profile.jvm_args = vec![
+			String::from("-Dfile.encoding=UTF-8"),
+			String::from("-XX:+UseG1GC"),
+			String::from("-XX:MaxGCPauseMillis=200"),
+		];
+		assert!(profile.validate(&java).is_ok());
+
+		profile.program_args = vec![String::from("--add-plugin=/tmp/evil.jar")];
+		assert!(profile.validate(&java).is_err());
+
+		profile.program_args = vec![String::from("--port"), String::from("25566")];
+		assert!(profile.validate(&java).is_ok());
+	}
+
+	#[tokio::test]
+	async fn java_path_must_be_an_installed_runtime() {
+		let root = std::env::temp_dir().join(format!("lunara-profile-jvm-{}", std::process::id()));
+		std::fs::create_dir_all(root.join("jdk21")).unwrap();
+		std::fs::write(
+			root.join("jdk21").join("release"),
+			"JAVA_VERSION=\"21.0.2\"\n",
+		)
+		.unwrap();
+
+		let java = JavaRegistry::discover_in(std::slice::from_ref(&root)).await;
+		let mut profile = LaunchProfile::default();
+
+		for path in ["", "/bin/sh", "java"] {
+			profile.java_path = Some(String::from(path));
+			assert!(profile.validate(&java).is_err(), "{} was accepted", path);
+		}
+
+		let installed = root.join("jdk21").join("bin").join("java");
+		profile.java_path = Some(installed.to_string_lossy().into_owned());
+		assert!(profile.validate(&java).is_ok());
+
+		std::fs::remove_dir_all(root).unwrap();
+	}
+
+	#[test]
+	fn missing_fields_fall_back_to_defaults() {
+		let profile: LaunchProfile = serde_json::from_str(r#"{"preset":"aikar"}"#).unwrap();
+
+		assert_eq!(profile.preset, FlagPreset::Aikar);
+		assert_eq!(profile.max_heap.as_deref(), Some(DEFAULT_MAX_HEAP));
+		assert!(profile.nogui);
+	}
+}
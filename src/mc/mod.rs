@@ -14,5 +14,17 @@ See the License for the specific language governing permissions and
 limitations under the License.
 
 */
+pub mod console;
+pub mod dependencies;
+pub mod descriptor;
+pub mod java_runtime;
+pub mod jobs;
+pub mod launch_profile;
+pub mod ping;
 pub mod plugin;
+pub mod rcon;
+pub mod registry;
+pub mod restart_policy;
 pub mod server;
+pub mod supervisor;
+pub mod upgrade;
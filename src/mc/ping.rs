@@ -0,0 +1,431 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use log::debug;
+use serde::Serialize;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+pub const PING_TIMEOUT: Duration = Duration::from_secs(2);
+// the status is already read by then, a silent server only loses the latency.
+const LATENCY_TIMEOUT: Duration = Duration::from_millis(500);
+
+// -1 asks the server to report its own protocol instead of rejecting ours.
+const ANY_PROTOCOL: i32 = -1;
+const LEGACY_PROTOCOL: u8 = 74;
+const STATUS_STATE: i32 = 1;
+const MAX_RESPONSE_LEN: i32 = 1 << 21;
+
+#[derive(Debug, Error)]
+pub enum PingError {
+	#[error("Ping connection failed: {0}")]
+	Io(#[from] std::io::Error),
+	#[error("Ping timed out")]
+	Timeout,
+	#[error("Malformed ping response: {0}")]
+	Malformed(&'static str),
+	#[error("Invalid status json: {0}")]
+	Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ServerPing {
+	pub version: String,
+	pub protocol: i32,
+	pub motd: String,
+	pub players_online: i64,
+	pub players_max: i64,
+	pub favicon: Option<String>,
+	pub latency_ms: Option<u64>,
+}
+
+/// Pings with the modern protocol and falls back to the 1.6 legacy ping.
+pub async fn ping(host: &str, port: u16) -> Result<ServerPing, PingError> {
+	match with_timeout(modern_status(host, port)).await {
+		Ok((mut status, mut stream)) => {
+			status.latency_ms = timeout(LATENCY_TIMEOUT, measure_latency(&mut stream))
+				.await
+				.ok()
+				.and_then(Result::ok);
+
+			Ok(status)
+		}
+		Err(error) => {
+			debug!(
+				"Modern ping to {}:{} failed, trying legacy. {}",
+				host, port, error
+			);
+
+			with_timeout(legacy_ping(host, port)).await
+		}
+	}
+}
+
+async fn with_timeout<T>(
+	future: impl Future<Output = Result<T, PingError>>,
+) -> Result<T, PingError> {
+	timeout(PING_TIMEOUT, future)
+		.await
+		.map_err(|_| PingError::Timeout)?
+}
+
+async fn modern_status(host: &str, port: u16) -> Result<(ServerPing, TcpStream), PingError> {
+	let mut stream = TcpStream::connect((host, port)).await?;
+
+	let mut handshake: Vec<u8> = vec![];
+	write_varint(&mut handshake, 0x00);
+	write_varint(&mut handshake, ANY_PROTOCOL);
+	write_string(&mut handshake, host);
+	handshake.extend_from_slice(&port.to_be_bytes());
+	write_varint(&mut handshake, STATUS_STATE);
+
+	stream.write_all(&frame(&handshake)).await?;
+	stream.write_all(&frame(&[0x00])).await?;
+
+	let _length = read_varint(&mut stream).await?;
+
+	if read_varint(&mut stream).await? != 0x00 {
+		return Err(PingError::Malformed("unexpected status packet id"));
+	}
+
+	let json_len = read_varint(&mut stream).await?;
+
+	if !(0..=MAX_RESPONSE_LEN).contains(&json_len) {
+		return Err(PingError::Malformed("invalid status length"));
+	}
+
+	let mut json = vec![0u8; json_len as usize];
+	stream.read_exact(&mut json).await?;
+
+	let status = parse_status(&serde_json::from_slice(&json)?)?;
+
+	Ok((status, stream))
+}
+
+async fn measure_latency(stream: &mut TcpStream) -> Result<u64, PingError> {
+	let sent = Instant::now();
+
+	let mut ping: Vec<u8> = vec![0x01];
+	ping.extend_from_slice(&0i64.to_be_bytes());
+	stream.write_all(&frame(&ping)).await?;
+
+	let _length = read_varint(stream).await?;
+	let _id = read_varint(stream).await?;
+	let mut payload = [0u8; 8];
+	stream.read_exact(&mut payload).await?;
+
+	Ok(sent.elapsed().as_millis() as u64)
+}
+
+async fn legacy_ping(host: &str, port: u16) -> Result<ServerPing, PingError> {
+	let mut stream = TcpStream::connect((host, port)).await?;
+	let sent = Instant::now();
+
+	let host_utf16: Vec<u8> = utf16_be(host);
+	let mut request: Vec<u8> = vec![0xFE, 0x01, 0xFA];
+
+	request.extend_from_slice(&11u16.to_be_bytes());
+	request.extend_from_slice(&utf16_be("MC|PingHost"));
+	request.extend_from_slice(&(7 + host_utf16.len() as u16).to_be_bytes());
+	request.push(LEGACY_PROTOCOL);
+	request.extend_from_slice(&(host.encode_utf16().count() as u16).to_be_bytes());
+	request.extend_from_slice(&host_utf16);
+	request.extend_from_slice(&(port as i32).to_be_bytes());
+
+	stream.write_all(&request).await?;
+
+	if stream.read_u8().await? != 0xFF {
+		return Err(PingError::Malformed("legacy kick packet expected"));
+	}
+
+	let chars = stream.read_u16().await? as usize;
+	let mut bytes = vec![0u8; chars * 2];
+	stream.read_exact(&mut bytes).await?;
+
+	let mut status = parse_legacy(&bytes)?;
+	status.latency_ms = Some(sent.elapsed().as_millis() as u64);
+
+	Ok(status)
+}
+
+fn parse_status(json: &Value) -> Result<ServerPing, PingError> {
+	let version = &json["version"];
+	let players = &json["players"];
+
+	Ok(ServerPing {
+		version: version["name"]
+			.as_str()
+			.ok_or(PingError::Malformed("missing version name"))?
+			.to_string(),
+		protocol: version["protocol"].as_i64().unwrap_or_default() as i32,
+		motd: flatten_text(&json["description"]),
+		players_online: players["online"].as_i64().unwrap_or_default(),
+		players_max: players["max"].as_i64().unwrap_or_default(),
+		favicon: json["favicon"].as_str().map(String::from),
+		latency_ms: None,
+	})
+}
+
+/// Legacy responses are `§1\0protocol\0version\0motd\0online\0max` in UTF-16BE.
+fn parse_legacy(bytes: &[u8]) -> Result<ServerPing, PingError> {
+	let units: Vec<u16> = bytes
+		.chunks_exact(2)
+		.map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+		.collect();
+
+	let text = String::from_utf16_lossy(&units);
+	let fields: Vec<&str> = text.split('\0').collect();
+
+	let [marker, protocol, version, motd, online, max] = fields[..] else {
+		return Err(PingError::Malformed("unexpected legacy field count"));
+	};
+
+	if marker != "§1" {
+		return Err(PingError::Malformed("missing legacy marker"));
+	}
+
+	Ok(ServerPing {
+		version: version.to_string(),
+		protocol: protocol.parse().unwrap_or_default(),
+		motd: motd.to_string(),
+		players_online: online.parse().unwrap_or_default(),
+		players_max: max.parse().unwrap_or_default(),
+		favicon: None,
+		latency_ms: None,
+	})
+}
+
+/// Descriptions are either plain strings or chat components with nested `extra` parts.
+fn flatten_text(component: &Value) -> String {
+	match component {
+		Value::String(text) => text.clone(),
+		Value::Array(parts) => parts.iter().map(flatten_text).collect(),
+		Value::Object(object) => {
+			let mut text: String = object
+				.get("text")
+				.and_then(Value::as_str)
+				.unwrap_or_default()
+				.to_string();
+
+			if let Some(extra) = object.get("extra") {
+				text.push_str(&flatten_text(extra));
+			}
+
+			text
+		}
+		_ => String::new(),
+	}
+}
+
+fn frame(payload: &[u8]) -> Vec<u8> {
+	let mut packet: Vec<u8> = vec![];
+	write_varint(&mut packet, payload.len() as i32);
+	packet.extend_from_slice(payload);
+	packet
+}
+
+fn write_varint(buf: &mut Vec<u8>, value: i32) {
+	let mut value = value as u32;
+
+	loop {
+		if value & !0x7F == 0 {
+			buf.push(value as u8);
+			return;
+		}
+
+		buf.push((value & 0x7F | 0x80) as u8);
+		value >>= 7;
+	}
+}
+
+async fn read_varint<R: AsyncRead + Unpin>(reader: &mut R) -> Result<i32, PingError> {
+	let mut value: u32 = 0;
+
+	for position in 0..5 {
+		let byte = reader.read_u8().await?;
+		value |= ((byte & 0x7F) as u32) << (7 * position);
+
+		if byte & 0x80 == 0 {
+			return Ok(value as i32);
+		}
+	}
+
+	Err(PingError::Malformed("varint too long"))
+}
+
+fn write_string(buf: &mut Vec<u8>, value: &str) {
+	write_varint(buf, value.len() as i32);
+	buf.extend_from_slice(value.as_bytes());
+}
+
+fn utf16_be(value: &str) -> Vec<u8> {
+	value.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+	use tokio::net::TcpListener;
+
+	#[tokio::test]
+	async fn varint_round_trip() {
+		for value in [0, 1, 127, 128, 255, 25565, 2097151, i32::MAX, -1] {
+			let mut buf = vec![];
+			write_varint(&mut buf, value);
+
+			assert_eq!(read_varint(&mut buf.as_slice()).await.unwrap(), value);
+		}
+	}
+
+	#[test]
+	fn varint_known_encodings() {
+		let mut buf = vec![];
+		write_varint(&mut buf, 300);
+		assert_eq!(buf, vec![0xAC, 0x02]);
+
+		let mut buf = vec![];
+		write_varint(&mut buf, -1);
+		assert_eq!(buf, vec![0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+	}
+
+	#[test]
+	fn flattens_chat_components() {
+		let description = json!({
+			"text": "Hello ",
+			"extra": [{"text": "brave "}, {"text": "new", "extra": [" world"]}]
+		});
+
+		assert_eq!(flatten_text(&description), "Hello brave new world");
+		assert_eq!(flatten_text(&json!("plain")), "plain");
+	}
+
+	#[test]
+	fn parses_status_json() {
+		let status = parse_status(&json!({
+			"version": {"name": "1.21.1", "protocol": 767},
+			"players": {"max": 20, "online": 3},
+			"description": {"text": "A Minecraft Server"},
+			"favicon": "data:image/png;base64,AAAA"
+		}))
+		.unwrap();
+
+		assert_eq!(status.version, "1.21.1");
+		assert_eq!(status.protocol, 767);
+		assert_eq!(status.players_online, 3);
+		assert_eq!(status.players_max, 20);
+		assert_eq!(status.motd, "A Minecraft Server");
+		assert_eq!(
+			status.favicon.as_deref(),
+			Some("data:image/png;base64,AAAA")
+		);
+	}
+
+	#[test]
+	fn parses_legacy_response() {
+		let bytes = utf16_be("§1\u{0}127\u{0}1.6.4\u{0}Old server\u{0}2\u{0}10");
+		let status = parse_legacy(&bytes).unwrap();
+
+		assert_eq!(status.version, "1.6.4");
+		assert_eq!(status.protocol, 127);
+		assert_eq!(status.motd, "Old server");
+		assert_eq!(status.players_online, 2);
+		assert_eq!(status.players_max, 10);
+	}
+
+	#[tokio::test]
+	async fn pings_modern_server() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		tokio::spawn(async move {
+			let (mut stream, _) = listener.accept().await.unwrap();
+
+			// handshake then status request.
+			for _ in 0..2 {
+				let length = read_varint(&mut stream).await.unwrap();
+				let mut packet = vec![0u8; length as usize];
+				stream.read_exact(&mut packet).await.unwrap();
+			}
+
+			let json = json!({
+				"version": {"name": "Paper 1.21.1", "protocol": 767},
+				"players": {"max": 50, "online": 7},
+				"description": "Lunara test"
+			})
+			.to_string();
+
+			let mut response = vec![0x00];
+			write_string(&mut response, &json);
+			stream.write_all(&frame(&response)).await.unwrap();
+
+			let length = read_varint(&mut stream).await.unwrap();
+			let mut ping = vec![0u8; length as usize];
+			stream.read_exact(&mut ping).await.unwrap();
+			stream.write_all(&frame(&ping)).await.unwrap();
+		});
+
+		let status = ping("127.0.0.1", port).await.unwrap();
+
+		assert_eq!(status.version, "Paper 1.21.1");
+		assert_eq!(status.players_online, 7);
+		assert_eq!(status.motd, "Lunara test");
+		assert!(status.latency_ms.is_some());
+	}
+
+	#[tokio::test]
+	async fn keeps_status_when_ping_goes_unanswered() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		tokio::spawn(async move {
+			let (mut stream, _) = listener.accept().await.unwrap();
+
+			for _ in 0..2 {
+				let length = read_varint(&mut stream).await.unwrap();
+				let mut packet = vec![0u8; length as usize];
+				stream.read_exact(&mut packet).await.unwrap();
+			}
+
+			let json = json!({"version": {"name": "Paper 1.21.1", "protocol": 767}}).to_string();
+
+			let mut response = vec![0x00];
+			write_string(&mut response, &json);
+			stream.write_all(&frame(&response)).await.unwrap();
+
+			// never answers the ping packet.
+			tokio::time::sleep(PING_TIMEOUT * 2).await;
+		});
+
+		let status = ping("127.0.0.1", port).await.unwrap();
+
+		assert_eq!(status.version, "Paper 1.21.1");
+		assert_eq!(status.latency_ms, None);
+	}
+
+	#[tokio::test]
+	async fn ping_fails_for_closed_port() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		drop(listener);
+
+		assert!(ping("127.0.0.1", port).await.is_err());
+	}
+}
@@ -14,27 +14,69 @@ See the License for the specific language governing permissions and
 limitations under the License.
 
 */
+use crate::http::upstream::Upstreams;
 use serde::{Deserialize, Serialize};
 
+/// Site a plugin was installed from.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginOrigin {
+	#[default]
+	Hangar,
+	Modrinth,
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Plugin {
 	name: String,
 	version: String,
+	#[serde(default)]
+	source: PluginOrigin,
 }
 
 impl Plugin {
 	pub fn new(name: String, version: String) -> Self {
-		Self { name, version }
+		Self {
+			name,
+			version,
+			source: PluginOrigin::Hangar,
+		}
+	}
+
+	pub fn with_source(mut self, source: PluginOrigin) -> Self {
+		self.source = source;
+		self
 	}
 
 	pub fn name(&self) -> &str {
 		&self.name
 	}
 
-	pub fn download_url(&self) -> String {
+	pub fn version(&self) -> &str {
+		&self.version
+	}
+
+	pub fn source(&self) -> PluginOrigin {
+		self.source
+	}
+
+	pub fn version_url(&self) -> String {
+		format!(
+			"{}/projects/{}/versions/{}",
+			Upstreams::current().hangar,
+			self.name,
+			self.version
+		)
+	}
+
+	/// Hangar's download for `platform`, `PAPER`, `VELOCITY` or `WATERFALL`.
+	pub fn download_url(&self, platform: &str) -> String {
 		format!(
-			"https://hangar.papermc.io/api/v1/projects/{}/versions/{}/PAPER/download",
-			self.name, self.version
+			"{}/projects/{}/versions/{}/{}/download",
+			Upstreams::current().hangar,
+			self.name,
+			self.version,
+			platform
 		)
 	}
 }
@@ -48,9 +90,10 @@ mod tests {
 		let plugin = Plugin {
 			name: String::from("TestPlugin"),
 			version: String::from("1.0.0"),
+			source: PluginOrigin::Hangar,
 		};
 
-		let url = plugin.download_url();
+		let url = plugin.download_url("PAPER");
 
 		assert_eq!(
 			url,
@@ -58,14 +101,25 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn plugin_version_url_format() {
+		let plugin = Plugin::new(String::from("TestPlugin"), String::from("1.0.0"));
+
+		assert_eq!(
+			plugin.version_url(),
+			"https://hangar.papermc.io/api/v1/projects/TestPlugin/versions/1.0.0"
+		);
+	}
+
 	#[test]
 	fn plugin_download_url_with_special_characters() {
 		let plugin = Plugin {
 			name: String::from("My-Plugin"),
 			version: String::from("2.1.3-SNAPSHOT"),
+			source: PluginOrigin::Hangar,
 		};
 
-		let url = plugin.download_url();
+		let url = plugin.download_url("PAPER");
 
 		assert!(url.contains("My-Plugin"));
 		assert!(url.contains("2.1.3-SNAPSHOT"));
@@ -76,11 +130,27 @@ mod tests {
 		let plugin = Plugin {
 			name: String::from("CloneTest"),
 			version: String::from("1.0"),
+			source: PluginOrigin::Hangar,
 		};
 
 		let cloned = plugin.clone();
 
-		assert_eq!(plugin.download_url(), cloned.download_url());
+		assert_eq!(plugin.download_url("PAPER"), cloned.download_url("PAPER"));
+	}
+
+	#[test]
+	fn plugin_source_defaults_to_hangar() {
+		let plugin: Plugin =
+			serde_json::from_str(r#"{"name":"LuckPerms","version":"5.4"}"#).unwrap();
+		let sodium = Plugin::new(String::from("sodium"), String::from("0.6"))
+			.with_source(PluginOrigin::Modrinth);
+
+		assert_eq!(plugin.source(), PluginOrigin::Hangar);
+		assert!(
+			serde_json::to_string(&sodium)
+				.unwrap()
+				.contains(r#""source":"modrinth""#)
+		);
 	}
 
 	#[test]
@@ -88,11 +158,15 @@ mod tests {
 		let plugin = Plugin {
 			name: String::from("SerdePlugin"),
 			version: String::from("3.2.1"),
+			source: PluginOrigin::Hangar,
 		};
 
 		let json = serde_json::to_string(&plugin).unwrap();
 		let deserialized: Plugin = serde_json::from_str(&json).unwrap();
 
-		assert_eq!(plugin.download_url(), deserialized.download_url());
+		assert_eq!(
+			plugin.download_url("PAPER"),
+			deserialized.download_url("PAPER")
+		);
 	}
 }
@@ -0,0 +1,296 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const RESPONSE: i32 = 0;
+const COMMAND: i32 = 2;
+const AUTH_RESPONSE: i32 = 2;
+const LOGIN: i32 = 3;
+
+// vanilla rejects commands longer than this.
+const MAX_COMMAND_LEN: usize = 1446;
+const MAX_PACKET_LEN: usize = 4096 + 10;
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum RconError {
+	#[error("RCON connection failed: {0}")]
+	Io(#[from] std::io::Error),
+	#[error("RCON authentication failed")]
+	AuthFailed,
+	#[error("RCON timed out")]
+	Timeout,
+	#[error("Malformed RCON packet: {0}")]
+	Malformed(&'static str),
+	#[error("Command too long: {0} bytes")]
+	CommandTooLong(usize),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Packet {
+	id: i32,
+	kind: i32,
+	body: String,
+}
+
+impl Packet {
+	fn encode(&self) -> Vec<u8> {
+		let length = (4 + 4 + self.body.len() + 2) as i32;
+		let mut bytes: Vec<u8> = Vec::with_capacity(length as usize + 4);
+
+		bytes.extend_from_slice(&length.to_le_bytes());
+		bytes.extend_from_slice(&self.id.to_le_bytes());
+		bytes.extend_from_slice(&self.kind.to_le_bytes());
+		bytes.extend_from_slice(self.body.as_bytes());
+		bytes.extend_from_slice(&[0, 0]);
+
+		bytes
+	}
+
+	/// Decodes everything after the length prefix.
+	fn decode(bytes: &[u8]) -> Result<Self, RconError> {
+		if bytes.len() < 10 {
+			return Err(RconError::Malformed("packet shorter than header"));
+		}
+
+		let id = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+		let kind = i32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+		let body = String::from_utf8_lossy(&bytes[8..bytes.len() - 2]).into_owned();
+
+		Ok(Self { id, kind, body })
+	}
+}
+
+/// Minimal Source RCON client as implemented by the vanilla server.
+pub struct RconClient {
+	stream: TcpStream,
+	next_id: i32,
+}
+
+impl RconClient {
+	pub async fn connect(addr: &str, password: &str) -> Result<Self, RconError> {
+		let stream = timeout(IO_TIMEOUT, TcpStream::connect(addr))
+			.await
+			.map_err(|_| RconError::Timeout)??;
+
+		let mut client = Self { stream, next_id: 1 };
+		client.authenticate(password).await?;
+
+		Ok(client)
+	}
+
+	async fn authenticate(&mut self, password: &str) -> Result<(), RconError> {
+		let id: i32 = self.take_id();
+		self.send(id, LOGIN, password).await?;
+
+		loop {
+			let packet = self.read_packet().await?;
+
+			if packet.kind != AUTH_RESPONSE {
+				continue;
+			}
+
+			if packet.id != id {
+				return Err(RconError::AuthFailed);
+			}
+
+			return Ok(());
+		}
+	}
+
+	/// Runs a command and returns its full output.
+	/// Long output is split across packets, so a second packet with an unknown type is sent
+	/// right after. The server answers in order, so its reply marks the end of the output.
+	pub async fn command(&mut self, command: &str) -> Result<String, RconError> {
+		if command.len() > MAX_COMMAND_LEN {
+			return Err(RconError::CommandTooLong(command.len()));
+		}
+
+		let id: i32 = self.take_id();
+		let sentinel: i32 = self.take_id();
+
+		self.send(id, COMMAND, command).await?;
+		self.send(sentinel, RESPONSE, "").await?;
+
+		let mut output = String::new();
+
+		loop {
+			let packet = self.read_packet().await?;
+
+			if packet.id == sentinel {
+				return Ok(output);
+			}
+
+			if packet.id == id {
+				output.push_str(&packet.body);
+			}
+		}
+	}
+
+	fn take_id(&mut self) -> i32 {
+		let id = self.next_id;
+		self.next_id = self.next_id.wrapping_add(1).max(1);
+		id
+	}
+
+	async fn send(&mut self, id: i32, kind: i32, body: &str) -> Result<(), RconError> {
+		let packet = Packet {
+			id,
+			kind,
+			body: body.to_string(),
+		};
+
+		self.stream.write_all(&packet.encode()).await?;
+		Ok(())
+	}
+
+	async fn read_packet(&mut self) -> Result<Packet, RconError> {
+		let mut length = [0u8; 4];
+
+		timeout(IO_TIMEOUT, self.stream.read_exact(&mut length))
+			.await
+			.map_err(|_| RconError::Timeout)??;
+
+		let length = i32::from_le_bytes(length);
+
+		if length < 10 || length as usize > MAX_PACKET_LEN {
+			return Err(RconError::Malformed("invalid packet length"));
+		}
+
+		let mut bytes = vec![0u8; length as usize];
+
+		timeout(IO_TIMEOUT, self.stream.read_exact(&mut bytes))
+			.await
+			.map_err(|_| RconError::Timeout)??;
+
+		Packet::decode(&bytes)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::net::TcpListener;
+
+	async fn read_test_packet(stream: &mut TcpStream) -> Packet {
+		let mut length = [0u8; 4];
+		stream.read_exact(&mut length).await.unwrap();
+
+		let mut bytes = vec![0u8; i32::from_le_bytes(length) as usize];
+		stream.read_exact(&mut bytes).await.unwrap();
+
+		Packet::decode(&bytes).unwrap()
+	}
+
+	async fn write_test_packet(stream: &mut TcpStream, id: i32, kind: i32, body: &str) {
+		let packet = Packet {
+			id,
+			kind,
+			body: body.to_string(),
+		};
+
+		stream.write_all(&packet.encode()).await.unwrap();
+	}
+
+	/// Accepts one client with password "hunter2" and answers every command in two parts.
+	async fn fake_server() -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap().to_string();
+
+		tokio::spawn(async move {
+			let (mut stream, _) = listener.accept().await.unwrap();
+
+			let login = read_test_packet(&mut stream).await;
+			let id = if login.body == "hunter2" {
+				login.id
+			} else {
+				-1
+			};
+			write_test_packet(&mut stream, id, AUTH_RESPONSE, "").await;
+
+			loop {
+				let packet = read_test_packet(&mut stream).await;
+
+				if packet.kind == COMMAND {
+					write_test_packet(&mut stream, packet.id, RESPONSE, "There are 0 of ").await;
+					write_test_packet(&mut stream, packet.id, RESPONSE, "20 players").await;
+				} else {
+					write_test_packet(&mut stream, packet.id, RESPONSE, "Unknown request 0").await;
+				}
+			}
+		});
+
+		addr
+	}
+
+	#[test]
+	fn packet_round_trip() {
+		let packet = Packet {
+			id: 7,
+			kind: COMMAND,
+			body: String::from("list"),
+		};
+
+		let bytes = packet.encode();
+
+		assert_eq!(
+			i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+			14
+		);
+		assert_eq!(Packet::decode(&bytes[4..]).unwrap(), packet);
+	}
+
+	#[test]
+	fn decode_rejects_short_packet() {
+		assert!(matches!(
+			Packet::decode(&[0, 0, 0]),
+			Err(RconError::Malformed(_))
+		));
+	}
+
+	#[tokio::test]
+	async fn command_joins_multi_packet_response() {
+		let addr = fake_server().await;
+		let mut client = RconClient::connect(&addr, "hunter2").await.unwrap();
+
+		let output = client.command("list").await.unwrap();
+
+		assert_eq!(output, "There are 0 of 20 players");
+	}
+
+	#[tokio::test]
+	async fn wrong_password_is_rejected() {
+		let addr = fake_server().await;
+		let result = RconClient::connect(&addr, "wrong").await;
+
+		assert!(matches!(result, Err(RconError::AuthFailed)));
+	}
+
+	#[tokio::test]
+	async fn long_command_is_rejected() {
+		let addr = fake_server().await;
+		let mut client = RconClient::connect(&addr, "hunter2").await.unwrap();
+
+		let result = client.command(&"a".repeat(MAX_COMMAND_LEN + 1)).await;
+
+		assert!(matches!(result, Err(RconError::CommandTooLong(_))));
+	}
+}
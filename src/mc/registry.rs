@@ -0,0 +1,549 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::database::Database;
+use crate::entity::{server_plugins, servers};
+use crate::mc::plugin::{Plugin, PluginOrigin};
+use crate::mc::server::{BuildInfo, MinecraftServer, ServerBrand};
+use log::{info, warn};
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseTransaction, DbErr, EntityTrait,
+	QueryFilter, Set, SqlErr, TransactionTrait,
+};
+use serde_json::Value;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::fs::{File, read, rename};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+pub(crate) const REGISTRY_LOCATION: &str = "servers.json";
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+	#[error("Server registry I/O failed: {0}")]
+	Io(#[from] std::io::Error),
+	#[error("Server registry is corrupt: {0}")]
+	Parse(#[from] serde_json::Error),
+	#[error("Server registry database error: {0}")]
+	Database(#[from] DbErr),
+	#[error("Server not found: {0}")]
+	NotFound(String),
+	#[error("A server named '{0}' already exists")]
+	DuplicateName(String),
+}
+
+#[derive(Clone)]
+enum Backend {
+	Json(PathBuf),
+	Database(Database),
+}
+
+/// Every server Lunara manages. Lives in Postgres when it's connected, otherwise in a JSON
+/// array on disk (degraded mode).
+/// JSON writes go through a temp file and a rename so a crash never leaves a half-written file,
+/// and every read-modify-write holds the lock so concurrent requests can't drop each other's changes.
+#[derive(Clone)]
+pub struct ServerRegistry {
+	backend: Backend,
+	lock: Arc<Mutex<()>>,
+}
+
+impl ServerRegistry {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self {
+			backend: Backend::Json(path.into()),
+			lock: Arc::new(Mutex::new(())),
+		}
+	}
+
+	pub fn with_database(database: Database) -> Self {
+		Self {
+			backend: Backend::Database(database),
+			lock: Arc::new(Mutex::new(())),
+		}
+	}
+
+	pub async fn load(&self) -> Result<Vec<MinecraftServer>, RegistryError> {
+		let _guard = self.lock.lock().await;
+
+		match &self.backend {
+			Backend::Json(path) => read_json(path).await,
+			Backend::Database(database) => {
+				let rows = servers::Entity::find()
+					.find_with_related(server_plugins::Entity)
+					.all(database.conn())
+					.await?;
+
+				rows.into_iter()
+					.map(|(row, plugins)| from_row(row, plugins))
+					.collect()
+			}
+		}
+	}
+
+	pub async fn get(&self, name: &str) -> Result<MinecraftServer, RegistryError> {
+		self.load()
+			.await?
+			.into_iter()
+			.find(|server| server.name() == name)
+			.ok_or_else(|| RegistryError::NotFound(name.to_string()))
+	}
+
+	pub async fn contains(&self, name: &str) -> Result<bool, RegistryError> {
+		Ok(self
+			.load()
+			.await?
+			.iter()
+			.any(|server| server.name() == name))
+	}
+
+	pub async fn insert(&self, server: MinecraftServer) -> Result<(), RegistryError> {
+		let _guard = self.lock.lock().await;
+
+		match &self.backend {
+			Backend::Json(path) => {
+				let mut servers = read_json(path).await?;
+
+				if servers.iter().any(|s| s.name() == server.name()) {
+					return Err(RegistryError::DuplicateName(server.name().to_string()));
+				}
+
+				servers.push(server);
+				write_json(path, &servers).await
+			}
+			Backend::Database(database) => {
+				let txn = database.conn().begin().await?;
+
+				if find_row(&txn, server.name()).await?.is_some() {
+					return Err(RegistryError::DuplicateName(server.name().to_string()));
+				}
+
+				insert_row(&txn, Uuid::new_v4(), &server).await?;
+				txn.commit().await?;
+
+				Ok(())
+			}
+		}
+	}
+
+	/// Applies `change` to the named server and persists the result.
+	pub async fn update<F>(&self, name: &str, change: F) -> Result<MinecraftServer, RegistryError>
+	where
+		F: FnOnce(&mut MinecraftServer),
+	{
+		let _guard = self.lock.lock().await;
+
+		match &self.backend {
+			Backend::Json(path) => {
+				let mut servers = read_json(path).await?;
+
+				let server = servers
+					.iter_mut()
+					.find(|s| s.name() == name)
+					.ok_or_else(|| RegistryError::NotFound(name.to_string()))?;
+
+				change(server);
+				let updated = server.clone();
+
+				if servers
+					.iter()
+					.filter(|s| s.name() == updated.name())
+					.count() > 1
+				{
+					return Err(RegistryError::DuplicateName(updated.name().to_string()));
+				}
+
+				write_json(path, &servers).await?;
+
+				Ok(updated)
+			}
+			Backend::Database(database) => {
+				let txn = database.conn().begin().await?;
+
+				let (row, plugins) = find_row(&txn, name)
+					.await?
+					.ok_or_else(|| RegistryError::NotFound(name.to_string()))?;
+
+				let id: Uuid = row.id;
+				let mut server = from_row(row, plugins)?;
+				change(&mut server);
+
+				if server.name() != name && find_row(&txn, server.name()).await?.is_some() {
+					return Err(RegistryError::DuplicateName(server.name().to_string()));
+				}
+
+				// replacing the rows keeps plugins in sync without diffing them.
+				servers::Entity::delete_by_id(id).exec(&txn).await?;
+				insert_row(&txn, id, &server).await?;
+				txn.commit().await?;
+
+				Ok(server)
+			}
+		}
+	}
+
+	pub async fn remove(&self, name: &str) -> Result<MinecraftServer, RegistryError> {
+		let _guard = self.lock.lock().await;
+
+		match &self.backend {
+			Backend::Json(path) => {
+				let mut servers = read_json(path).await?;
+
+				let index = servers
+					.iter()
+					.position(|s| s.name() == name)
+					.ok_or_else(|| RegistryError::NotFound(name.to_string()))?;
+
+				let removed = servers.remove(index);
+				write_json(path, &servers).await?;
+
+				Ok(removed)
+			}
+			Backend::Database(database) => {
+				let (row, plugins) = find_row(database.conn(), name)
+					.await?
+					.ok_or_else(|| RegistryError::NotFound(name.to_string()))?;
+
+				let id: Uuid = row.id;
+				let removed = from_row(row, plugins)?;
+
+				servers::Entity::delete_by_id(id)
+					.exec(database.conn())
+					.await?;
+
+				Ok(removed)
+			}
+		}
+	}
+
+	/// Moves servers from a leftover JSON registry into the database, skipping names that are
+	/// already there. The file is renamed afterwards so this only ever runs once.
+	pub async fn import_json(&self, path: impl AsRef<Path>) -> Result<usize, RegistryError> {
+		let path: &Path = path.as_ref();
+
+		if matches!(self.backend, Backend::Json(_)) || !path.exists() {
+			return Ok(0);
+		}
+
+		let mut imported: usize = 0;
+
+		for server in read_json(path).await? {
+			match self.insert(server).await {
+				Ok(()) => imported += 1,
+				Err(RegistryError::DuplicateName(name)) => {
+					warn!("Skipping import of {}, it is already in the database", name);
+				}
+				Err(error) => return Err(error),
+			}
+		}
+
+		let mut done = path.to_path_buf().into_os_string();
+		done.push(".imported");
+		rename(path, &done).await?;
+
+		info!("Imported {} servers from {}", imported, path.display());
+
+		Ok(imported)
+	}
+}
+
+// a missing file is just an empty registry, e.g. on first launch.
+async fn read_json(path: &Path) -> Result<Vec<MinecraftServer>, RegistryError> {
+	match read(path).await {
+		Ok(contents) if contents.iter().all(u8::is_ascii_whitespace) => Ok(vec![]),
+		Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+		Err(error) if error.kind() == ErrorKind::NotFound => Ok(vec![]),
+		Err(error) => Err(error.into()),
+	}
+}
+
+async fn write_json(path: &Path, servers: &[MinecraftServer]) -> Result<(), RegistryError> {
+	let json = serde_json::to_vec_pretty(servers)?;
+
+	let mut temp_path = path.to_path_buf().into_os_string();
+	temp_path.push(".tmp");
+
+	let mut file = File::create(&temp_path).await?;
+	file.write_all(&json).await?;
+	file.sync_all().await?;
+
+	rename(&temp_path, path).await?;
+	Ok(())
+}
+
+async fn find_row(
+	conn: &impl ConnectionTrait,
+	name: &str,
+) -> Result<Option<(servers::Model, Vec<server_plugins::Model>)>, RegistryError> {
+	let mut rows = servers::Entity::find()
+		.filter(servers::Column::Name.eq(name))
+		.find_with_related(server_plugins::Entity)
+		.all(conn)
+		.await?;
+
+	Ok(rows.pop())
+}
+
+async fn insert_row(
+	txn: &DatabaseTransaction,
+	id: Uuid,
+	server: &MinecraftServer,
+) -> Result<(), RegistryError> {
+	servers::ActiveModel {
+		id: Set(id),
+		name: Set(server.name().to_string()),
+		brand: Set(serde_json::to_value(server.brand())?
+			.as_str()
+			.unwrap_or_default()
+			.to_string()),
+		version: Set(server.version().to_string()),
+		build: Set(server.build_info().build.clone()),
+		channel: Set(server.build_info().channel.as_str().to_string()),
+		directory: Set(server.directory()),
+		java_major: Set(server.recorded_java_major().map(|major| major as i32)),
+		restart_policy: Set(serde_json::to_value(server.restart_policy())?),
+		launch_profile: Set(serde_json::to_value(server.launch_profile())?),
+		launch_target: Set(server
+			.recorded_launch_target()
+			.map(serde_json::to_value)
+			.transpose()?),
+	}
+	.insert(txn)
+	.await
+	.map_err(|error| match error.sql_err() {
+		// another request took the name between the lookup and this insert.
+		Some(SqlErr::UniqueConstraintViolation(_)) => {
+			RegistryError::DuplicateName(server.name().to_string())
+		}
+		_ => error.into(),
+	})?;
+
+	for plugin in server.plugins().into_iter().flatten() {
+		server_plugins::ActiveModel {
+			id: Set(Uuid::new_v4()),
+			server_id: Set(id),
+			name: Set(plugin.name().to_string()),
+			version: Set(plugin.version().to_string()),
+			source: Set(serde_json::to_value(plugin.source())?
+				.as_str()
+				.unwrap_or_default()
+				.to_string()),
+		}
+		.insert(txn)
+		.await?;
+	}
+
+	Ok(())
+}
+
+fn from_row(
+	row: servers::Model,
+	plugins: Vec<server_plugins::Model>,
+) -> Result<MinecraftServer, RegistryError> {
+	let brand: ServerBrand = serde_json::from_value(Value::String(row.brand))?;
+	let mut server = MinecraftServer::new();
+
+	server
+		.with_name(Some(row.name))
+		.with_brand(brand)
+		.with_version(BuildInfo {
+			version: row.version,
+			build: row.build,
+			channel: serde_json::from_value(Value::String(row.channel))?,
+		})
+		.with_restart_policy(serde_json::from_value(row.restart_policy)?)
+		.with_launch_profile(serde_json::from_value(row.launch_profile)?)
+		.with_launch_target(row.launch_target.map(serde_json::from_value).transpose()?)
+		.with_directory(Some(row.directory));
+
+	if let Some(major) = row.java_major {
+		server.with_java_major(major as u32);
+	}
+
+	for plugin in plugins {
+		let source: PluginOrigin = serde_json::from_value(Value::String(plugin.source))?;
+
+		server.with_plugin(Plugin::new(plugin.name, plugin.version).with_source(source));
+	}
+
+	Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mc::server::ReleaseChannel;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+	fn temp_registry() -> ServerRegistry {
+		let path = std::env::temp_dir().join(format!(
+			"lunara-registry-{}-{}.json",
+			std::process::id(),
+			NEXT.fetch_add(1, Ordering::Relaxed)
+		));
+
+		ServerRegistry::new(path)
+	}
+
+	impl ServerRegistry {
+		fn json_path(&self) -> &Path {
+			match &self.backend {
+				Backend::Json(path) => path,
+				Backend::Database(_) => unreachable!("tests only use the JSON backend"),
+			}
+		}
+	}
+
+	fn server(name: &str) -> MinecraftServer {
+		let mut server = MinecraftServer::new();
+		server.with_name(Some(name.to_string()));
+		server
+	}
+
+	#[tokio::test]
+	async fn missing_file_is_empty() {
+		let registry = temp_registry();
+
+		assert!(registry.load().await.unwrap().is_empty());
+		assert!(matches!(
+			registry.get("lobby").await,
+			Err(RegistryError::NotFound(_))
+		));
+	}
+
+	#[tokio::test]
+	async fn insert_update_remove_round_trip() {
+		let registry = temp_registry();
+
+		registry.insert(server("lobby")).await.unwrap();
+		registry.insert(server("survival")).await.unwrap();
+
+		let updated = registry
+			.update("lobby", |s| {
+				s.with_version(BuildInfo {
+					version: String::from("1.21.1"),
+					..Default::default()
+				});
+			})
+			.await
+			.unwrap();
+		assert_eq!(updated.version(), "1.21.1");
+		assert_eq!(registry.get("lobby").await.unwrap().version(), "1.21.1");
+
+		registry.remove("lobby").await.unwrap();
+
+		let names: Vec<String> = registry
+			.load()
+			.await
+			.unwrap()
+			.iter()
+			.map(|s| s.name().to_string())
+			.collect();
+		assert_eq!(names, ["survival"]);
+
+		tokio::fs::remove_file(registry.json_path()).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn rejects_duplicate_names() {
+		let registry = temp_registry();
+
+		registry.insert(server("lobby")).await.unwrap();
+
+		assert!(matches!(
+			registry.insert(server("lobby")).await,
+			Err(RegistryError::DuplicateName(_))
+		));
+
+		tokio::fs::remove_file(registry.json_path()).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn concurrent_inserts_are_not_lost() {
+		let registry = temp_registry();
+
+		let inserts: Vec<_> = (0..16)
+			.map(|i| {
+				let registry = registry.clone();
+				tokio::spawn(async move { registry.insert(server(&format!("s{}", i))).await })
+			})
+			.collect();
+
+		for insert in inserts {
+			insert.await.unwrap().unwrap();
+		}
+
+		assert_eq!(registry.load().await.unwrap().len(), 16);
+
+		tokio::fs::remove_file(registry.json_path()).await.unwrap();
+	}
+
+	#[test]
+	fn rows_convert_back_to_servers() {
+		let row = servers::Model {
+			id: Uuid::new_v4(),
+			name: String::from("lobby"),
+			brand: String::from("Paper"),
+			version: String::from("1.21.1"),
+			build: Some(String::from("132")),
+			channel: String::from("experimental"),
+			directory: String::from("/srv/lunara/lobby"),
+			java_major: Some(21),
+			restart_policy: serde_json::json!({"mode": "always", "backoff_secs": 5}),
+			launch_profile: serde_json::json!({"max_heap": "4G"}),
+			launch_target: None,
+		};
+		let plugin = server_plugins::Model {
+			id: Uuid::new_v4(),
+			server_id: row.id,
+			name: String::from("LuckPerms"),
+			version: String::from("5.4"),
+			source: String::from("modrinth"),
+		};
+
+		let server = from_row(row, vec![plugin]).unwrap();
+
+		assert_eq!(server.name(), "lobby");
+		assert_eq!(server.directory(), "/srv/lunara/lobby");
+		assert_eq!(server.brand(), &ServerBrand::Paper);
+		assert_eq!(server.java_major(), 21);
+		assert_eq!(server.build_info().build.as_deref(), Some("132"));
+		assert_eq!(server.build_info().channel, ReleaseChannel::Experimental);
+		assert_eq!(
+			server.restart_policy(),
+			&crate::mc::restart_policy::RestartPolicy::Always { backoff_secs: 5 }
+		);
+		assert_eq!(server.launch_profile().max_heap.as_deref(), Some("4G"));
+		assert_eq!(server.plugins().unwrap()[0].name(), "LuckPerms");
+		assert_eq!(
+			server.plugins().unwrap()[0].source(),
+			PluginOrigin::Modrinth
+		);
+	}
+
+	#[tokio::test]
+	async fn json_backend_skips_import() {
+		let registry = temp_registry();
+
+		assert_eq!(registry.import_json(REGISTRY_LOCATION).await.unwrap(), 0);
+	}
+}
@@ -0,0 +1,123 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// What the supervisor does when a server exits without being asked to.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RestartPolicy {
+	#[default]
+	Never,
+	OnFailure {
+		max_retries: u32,
+		backoff_secs: u64,
+	},
+	Always {
+		backoff_secs: u64,
+	},
+}
+
+impl RestartPolicy {
+	/// Returns how long to wait before the next restart, or `None` to leave the server down.
+	/// `attempt` counts automatic restarts since the last manual start.
+	pub fn delay(&self, attempt: u32, crashed: bool) -> Option<Duration> {
+		match self {
+			RestartPolicy::Never => None,
+			RestartPolicy::OnFailure {
+				max_retries,
+				backoff_secs,
+			} => {
+				if !crashed || attempt >= *max_retries {
+					return None;
+				}
+
+				Some(backoff(*backoff_secs, attempt))
+			}
+			RestartPolicy::Always { backoff_secs } => Some(backoff(*backoff_secs, attempt)),
+		}
+	}
+}
+
+// doubles every attempt so a boot loop doesn't hammer the host.
+fn backoff(base_secs: u64, attempt: u32) -> Duration {
+	let factor: u64 = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
+
+	Duration::from_secs(base_secs.saturating_mul(factor)).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn never_does_not_restart() {
+		assert_eq!(RestartPolicy::Never.delay(0, true), None);
+	}
+
+	#[test]
+	fn on_failure_backs_off_until_retries_run_out() {
+		let policy = RestartPolicy::OnFailure {
+			max_retries: 3,
+			backoff_secs: 5,
+		};
+
+		assert_eq!(policy.delay(0, true), Some(Duration::from_secs(5)));
+		assert_eq!(policy.delay(1, true), Some(Duration::from_secs(10)));
+		assert_eq!(policy.delay(2, true), Some(Duration::from_secs(20)));
+		assert_eq!(policy.delay(3, true), None);
+	}
+
+	#[test]
+	fn on_failure_ignores_clean_exit() {
+		let policy = RestartPolicy::OnFailure {
+			max_retries: 3,
+			backoff_secs: 5,
+		};
+
+		assert_eq!(policy.delay(0, false), None);
+	}
+
+	#[test]
+	fn always_restarts_with_capped_backoff() {
+		let policy = RestartPolicy::Always { backoff_secs: 10 };
+
+		assert_eq!(policy.delay(0, false), Some(Duration::from_secs(10)));
+		assert_eq!(policy.delay(100, true), Some(MAX_BACKOFF));
+	}
+
+	#[test]
+	fn policy_serializes_with_mode_tag() {
+		let policy = RestartPolicy::OnFailure {
+			max_retries: 2,
+			backoff_secs: 1,
+		};
+
+		let json = serde_json::to_string(&policy).unwrap();
+
+		assert_eq!(
+			json,
+			r#"{"mode":"on_failure","max_retries":2,"backoff_secs":1}"#
+		);
+		assert_eq!(
+			serde_json::from_str::<RestartPolicy>(&json).unwrap(),
+			policy
+		);
+	}
+}
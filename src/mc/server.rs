@@ -14,25 +14,90 @@ See the License for the specific language governing permissions and
 limitations under the License.
 
 */
+use crate::http::artifact_cache::ArtifactCache;
+use crate::http::plugin_source::{PluginDependency, PluginSource, ResolvedPlugin};
+use crate::http::server_provider::ServerProvider;
+use crate::keyring_service::KeyringService;
+use crate::mc::java_runtime::{JavaRegistry, fallback_major};
+use crate::mc::launch_profile::{LaunchProfile, LaunchTarget};
+use crate::mc::ping::{PingError, ServerPing, ping};
 use crate::mc::plugin::Plugin;
+use crate::mc::rcon::{RconClient, RconError};
+use crate::mc::restart_policy::RestartPolicy;
+use crate::mc::supervisor::{ServerStatus, Supervisor, SupervisorError};
 use log::info;
-use reqwest::{Client, Response};
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
-use std::fs::File;
-use std::io::Write;
-use tokio::fs::{create_dir_all, read_to_string, remove_dir_all};
-use tokio::process::Command;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::fs::{create_dir_all, read_to_string, remove_dir_all, write};
+use tokio::process::{Child, Command};
 
-#[derive(Debug, Deserialize, Clone, Serialize, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Clone, Serialize, Eq, PartialEq, Hash)]
 pub enum ServerBrand {
 	Vanilla,
 	Paper,
+	Purpur,
+	Folia,
+	Fabric,
+	Velocity,
+	Waterfall,
+	Forge,
+	NeoForge,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+impl ServerBrand {
+	pub(crate) fn supports_plugins(&self) -> bool {
+		!self.plugin_loaders().is_empty()
+	}
+}
+
+/// Which upstream builds may be picked when no build number is pinned.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseChannel {
+	#[default]
+	Default,
+	Experimental,
+}
+
+impl ReleaseChannel {
+	pub(crate) fn as_str(&self) -> &'static str {
+		match self {
+			ReleaseChannel::Default => "default",
+			ReleaseChannel::Experimental => "experimental",
+		}
+	}
+
+	/// Experimental takes the newest build of any channel, default only stable ones.
+	/// Builds without a channel are treated as stable.
+	pub(crate) fn accepts(&self, upstream: Option<&str>) -> bool {
+		match self {
+			ReleaseChannel::Default => upstream.is_none_or(|channel| {
+				channel.eq_ignore_ascii_case("default") || channel.eq_ignore_ascii_case("stable")
+			}),
+			ReleaseChannel::Experimental => true,
+		}
+	}
+}
+
+impl std::fmt::Display for ReleaseChannel {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct BuildInfo {
 	pub(crate) version: String,
+	/// Upstream build number, the newest one on `channel` when unset.
+	#[serde(default)]
+	pub(crate) build: Option<String>,
+	#[serde(default)]
+	pub(crate) channel: ReleaseChannel,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -41,11 +106,25 @@ pub struct MinecraftServer {
 	build: BuildInfo,
 	name: Option<String>,
 	plugins: Option<Vec<Plugin>>,
+	#[serde(default)]
+	restart_policy: RestartPolicy,
+	#[serde(default)]
+	launch_profile: LaunchProfile,
+	#[serde(default)]
+	launch_target: Option<LaunchTarget>,
+	#[serde(default)]
+	java_major: Option<u32>,
+	/// Where the server lives, `PARENT_PATH/<name>` when unset.
+	#[serde(default)]
+	directory: Option<String>,
 	#[serde(skip)]
 	log_cache: Option<String>,
 }
 
 pub(crate) const PARENT_PATH: &str = "/app/servers";
+const DEFAULT_RCON_PORT: &str = "25575";
+const DEFAULT_SERVER_PORT: u16 = 25565;
+pub(crate) const EULA_URL: &str = "https://aka.ms/MinecraftEULA";
 
 impl MinecraftServer {
 	pub(crate) fn new() -> Self {
@@ -53,40 +132,204 @@ impl MinecraftServer {
 			brand: ServerBrand::Vanilla,
 			build: BuildInfo {
 				version: String::from("NaN"),
+				..Default::default()
 			},
 			name: None,
 			plugins: None,
+			restart_policy: RestartPolicy::Never,
+			launch_profile: LaunchProfile::default(),
+			launch_target: None,
+			java_major: None,
+			directory: None,
 			log_cache: None,
 		}
 	}
+	/// Downloads just `plugin`, returning what its site says it depends on.
 	pub(crate) async fn add_plugin(
 		&self,
+		cache: &ArtifactCache,
 		plugin: &Plugin,
-	) -> Result<(), Box<dyn Error + Sync + Send>> {
-		let plugins_dir = format!("{}/plugins", self.directory());
+	) -> Result<Vec<PluginDependency>, Box<dyn Error + Sync + Send>> {
+		let dest: String = self
+			.plugin_path(plugin.name())
+			.ok_or_else(|| format!("invalid plugin name '{}'", plugin.name()))?;
+
+		let plugins_dir = format!("{}/{}", self.directory(), self.brand().plugin_dir());
 		create_dir_all(&plugins_dir).await?;
 
-		let url: String = plugin.download_url();
-		let dest: String = format!("{}/{}.jar", plugins_dir, plugin.name());
+		let client: Client = Client::new();
 
-		let response: Response = Client::new().get(&url).send().await?;
-		let bytes = response.bytes().await?;
+		let resolved: ResolvedPlugin = plugin
+			.source()
+			.resolve(&client, plugin, self.brand().plugin_loaders())
+			.await?;
 
-		let mut file: File = File::create(&dest)?;
-		file.write_all(&bytes)?;
+		cache
+			.fetch(&client, &resolved.target, Path::new(&dest), None)
+			.await?;
 
 		info!("Plugin downloaded to {}", dest);
-		Ok(())
+		Ok(resolved.dependencies)
+	}
+
+	/// `None` when `name` would leave the plugin folder, names can come from remote metadata.
+	pub(crate) fn plugin_path(&self, name: &str) -> Option<String> {
+		if !is_plugin_file_name(name) {
+			return None;
+		}
+
+		Some(format!(
+			"{}/{}/{}.jar",
+			self.directory(),
+			self.brand().plugin_dir(),
+			name
+		))
 	}
 
-	pub(crate) async fn turn_on(&self) -> Result<(), Box<dyn Error + Sync + Send>> {
-		Command::new("java")
-			.arg("-jar")
-			.arg(format!("{}.jar", self.name()))
+	pub(crate) async fn turn_on(&self, runtimes: &JavaRegistry) -> Result<Child, SupervisorError> {
+		if !self.eula_accepted().await {
+			return Err(SupervisorError::EulaNotAccepted(self.name().to_string()));
+		}
+
+		let profile: &LaunchProfile = &self.launch_profile;
+
+		// profiles stored before they were checked are held to the same rules.
+		profile.validate(runtimes).map_err(SupervisorError::Spawn)?;
+
+		let java: String = match &profile.java_path {
+			Some(path) => path.clone(),
+			None => runtimes.executable_for(self.java_major()),
+		};
+
+		info!("Launching {} with {}", self.name(), java);
+
+		let child: Child = Command::new(java)
+			.args(profile.arguments_for(&self.launch_target()))
 			.current_dir(self.directory())
-			.spawn()?;
+			.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()
+			.map_err(|e| SupervisorError::Spawn(e.to_string()))?;
 
-		Ok(())
+		Ok(child)
+	}
+
+	pub(crate) async fn eula_accepted(&self) -> bool {
+		let path = format!("{}/eula.txt", self.directory());
+
+		match read_to_string(&path).await {
+			Ok(content) => parse_properties(&content)
+				.get("eula")
+				.is_some_and(|value| value.eq_ignore_ascii_case("true")),
+			Err(_) => false,
+		}
+	}
+
+	pub(crate) async fn write_eula(&self, accepted: bool) -> std::io::Result<()> {
+		create_dir_all(self.directory()).await?;
+
+		write(
+			format!("{}/eula.txt", self.directory()),
+			format!(
+				"#By changing the setting below to TRUE you are indicating your agreement to our EULA ({}).\neula={}\n",
+				EULA_URL, accepted
+			),
+		)
+		.await
+	}
+
+	pub(crate) async fn stop(
+		&self,
+		supervisor: &Supervisor,
+		limit: Duration,
+	) -> Result<ServerStatus, SupervisorError> {
+		supervisor.stop(self.name(), limit).await
+	}
+
+	pub(crate) async fn kill(
+		&self,
+		supervisor: &Supervisor,
+	) -> Result<ServerStatus, SupervisorError> {
+		supervisor.kill(self.name()).await
+	}
+
+	pub(crate) async fn restart(
+		&self,
+		supervisor: &Supervisor,
+		limit: Duration,
+	) -> Result<ServerStatus, SupervisorError> {
+		supervisor.restart(self, limit).await
+	}
+
+	pub(crate) async fn properties(
+		&self,
+	) -> Result<HashMap<String, String>, Box<dyn Error + Sync + Send>> {
+		let path = format!("{}/server.properties", self.directory());
+		let content = read_to_string(&path).await?;
+
+		Ok(parse_properties(&content))
+	}
+
+	/// Keyring entry holding this server's RCON password.
+	pub(crate) fn rcon_key(&self) -> String {
+		format!("rcon.{}", self.name())
+	}
+
+	/// Opens an RCON session using the password kept in the keyring.
+	/// The password is taken from server.properties the first time and whenever the stored one is rejected.
+	pub(crate) async fn rcon(
+		&self,
+		keyring: &KeyringService,
+	) -> Result<RconClient, Box<dyn Error + Sync + Send>> {
+		let properties = self.properties().await?;
+
+		if properties.get("enable-rcon").map(String::as_str) != Some("true") {
+			return Err(format!("enable-rcon is not set for {}", self.name()).into());
+		}
+
+		let key: String = self.rcon_key();
+		let configured: Option<&String> = properties.get("rcon.password").filter(|p| !p.is_empty());
+
+		let port: &str = properties
+			.get("rcon.port")
+			.map(String::as_str)
+			.unwrap_or(DEFAULT_RCON_PORT);
+		let addr: String = format!("127.0.0.1:{}", port);
+
+		if keyring.secret_exists(&key).await {
+			let stored: String = keyring.get_secret(&key).await?;
+
+			match RconClient::connect(&addr, &stored).await {
+				// server.properties may have a new password.
+				Err(RconError::AuthFailed) if configured.is_some_and(|p| *p != stored) => {
+					info!(
+						"Stored RCON password for {} was rejected, re-reading server.properties",
+						self.name()
+					);
+				}
+				result => return Ok(result?),
+			}
+		}
+
+		let password: &String =
+			configured.ok_or_else(|| format!("rcon.password is not set for {}", self.name()))?;
+		let client = RconClient::connect(&addr, password).await?;
+
+		keyring.set_secret(&key, password).await?;
+		Ok(client)
+	}
+
+	/// Asks the running server for its MOTD, version and player count.
+	pub(crate) async fn ping(&self) -> Result<ServerPing, PingError> {
+		let port: u16 = self
+			.properties()
+			.await
+			.ok()
+			.and_then(|p| p.get("server-port")?.parse().ok())
+			.unwrap_or(DEFAULT_SERVER_PORT);
+
+		ping("127.0.0.1", port).await
 	}
 
 	pub(crate) async fn delete(&self) -> Result<(), Box<dyn Error + Sync + Send>> {
@@ -100,7 +343,12 @@ impl MinecraftServer {
 		&self,
 		target: Plugin,
 	) -> Result<(), Box<dyn Error + Sync + Send>> {
-		let path_str: &String = &format!("{}/plugins/{}.jar", &self.directory(), target.name());
+		let path_str: &String = &format!(
+			"{}/{}/{}.jar",
+			&self.directory(),
+			self.brand().plugin_dir(),
+			target.name()
+		);
 
 		remove_dir_all(path_str).await?;
 		Ok(())
@@ -128,6 +376,12 @@ impl MinecraftServer {
 		self
 	}
 
+	/// Pins the build that was actually installed.
+	pub(crate) fn with_build_id(&mut self, build: Option<String>) -> &mut Self {
+		self.build.build = build;
+		self
+	}
+
 	pub(crate) fn with_name(&mut self, name: Option<String>) -> &mut Self {
 		self.name = name;
 		self
@@ -138,6 +392,40 @@ impl MinecraftServer {
 		self
 	}
 
+	/// Records an installed plugin, replacing an older entry with the same name.
+	pub(crate) fn with_plugin(&mut self, plugin: Plugin) -> &mut Self {
+		let plugins = self.plugins.get_or_insert_with(Vec::new);
+
+		plugins.retain(|p| p.name() != plugin.name());
+		plugins.push(plugin);
+		self
+	}
+
+	pub(crate) fn with_restart_policy(&mut self, policy: RestartPolicy) -> &mut Self {
+		self.restart_policy = policy;
+		self
+	}
+
+	pub(crate) fn with_launch_profile(&mut self, profile: LaunchProfile) -> &mut Self {
+		self.launch_profile = profile;
+		self
+	}
+
+	pub(crate) fn with_launch_target(&mut self, target: Option<LaunchTarget>) -> &mut Self {
+		self.launch_target = target;
+		self
+	}
+
+	pub(crate) fn with_java_major(&mut self, major: u32) -> &mut Self {
+		self.java_major = Some(major);
+		self
+	}
+
+	pub(crate) fn with_directory(&mut self, directory: Option<String>) -> &mut Self {
+		self.directory = directory;
+		self
+	}
+
 	pub(crate) fn build_info(&self) -> &BuildInfo {
 		&self.build
 	}
@@ -146,6 +434,36 @@ impl MinecraftServer {
 		self.plugins.as_ref()
 	}
 
+	pub(crate) fn restart_policy(&self) -> &RestartPolicy {
+		&self.restart_policy
+	}
+
+	pub(crate) fn launch_profile(&self) -> &LaunchProfile {
+		&self.launch_profile
+	}
+
+	/// What the installer recorded, otherwise the renamed `<name>.jar`.
+	pub(crate) fn launch_target(&self) -> LaunchTarget {
+		self.launch_target
+			.clone()
+			.unwrap_or_else(|| LaunchTarget::Jar(format!("{}.jar", self.name())))
+	}
+
+	pub(crate) fn recorded_launch_target(&self) -> Option<&LaunchTarget> {
+		self.launch_target.as_ref()
+	}
+
+	/// Java major the server needs, from Mojang's metadata when it was recorded at creation.
+	pub(crate) fn java_major(&self) -> u32 {
+		self.java_major
+			.unwrap_or_else(|| fallback_major(self.version()))
+	}
+
+	/// Only what was recorded at creation, without the version based guess.
+	pub(crate) fn recorded_java_major(&self) -> Option<u32> {
+		self.java_major
+	}
+
 	pub(crate) fn name(&self) -> &str {
 		self.name.as_deref().unwrap_or("No server name found. NaN")
 	}
@@ -163,14 +481,61 @@ impl MinecraftServer {
 	}
 
 	pub(crate) fn directory(&self) -> String {
-		format!("{}/{}", PARENT_PATH, self.name())
+		self.directory
+			.clone()
+			.unwrap_or_else(|| format!("{}/{}", PARENT_PATH, self.name()))
 	}
 }
 
+// plugin names become jar file names, none may reach outside the plugin folder.
+pub(crate) fn is_plugin_file_name(name: &str) -> bool {
+	!name.is_empty() && !name.contains(['/', '\\', '\0']) && !name.contains("..")
+}
+
+fn parse_properties(content: &str) -> HashMap<String, String> {
+	content
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.filter_map(|line| line.split_once('='))
+		.map(|(key, value)| (key.trim().to_string(), value.trim().replace("\\:", ":")))
+		.collect()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	#[test]
+	fn parses_server_properties() {
+		let properties = parse_properties(
+			"#Minecraft server properties\nenable-rcon=true\nrcon.port=25580\nmotd=A\\: server\nlevel-seed=\n",
+		);
+
+		assert_eq!(properties["enable-rcon"], "true");
+		assert_eq!(properties["rcon.port"], "25580");
+		assert_eq!(properties["motd"], "A: server");
+		assert_eq!(properties["level-seed"], "");
+		assert_eq!(properties.len(), 4);
+	}
+
+	#[test]
+	fn plugin_paths_stay_in_the_plugin_folder() {
+		let mut server = MinecraftServer::new();
+		server
+			.with_name(Some(String::from("lobby")))
+			.with_brand(ServerBrand::Paper);
+
+		assert_eq!(
+			server.plugin_path("LuckPerms").as_deref(),
+			Some("/app/servers/lobby/plugins/LuckPerms.jar")
+		);
+
+		for name in ["", "..", "../../eula", "sub/plugin", "sub\\plugin"] {
+			assert_eq!(server.plugin_path(name), None, "{}", name);
+		}
+	}
+
 	#[test]
 	fn new_server_has_defaults() {
 		let server = MinecraftServer::new();
@@ -202,6 +567,7 @@ mod tests {
 		let mut server = MinecraftServer::new();
 		server.with_version(BuildInfo {
 			version: String::from("1.20.4"),
+			..Default::default()
 		});
 
 		assert_eq!(server.version(), "1.20.4");
@@ -215,6 +581,7 @@ mod tests {
 			.with_brand(ServerBrand::Paper)
 			.with_version(BuildInfo {
 				version: String::from("1.19.2"),
+				..Default::default()
 			});
 
 		assert_eq!(server.name(), "ChainTest");
@@ -235,6 +602,7 @@ mod tests {
 		let mut server = MinecraftServer::new();
 		server.with_version(BuildInfo {
 			version: String::from("1.21"),
+			..Default::default()
 		});
 
 		assert_eq!(server.build_info().version, "1.21");
@@ -248,6 +616,7 @@ mod tests {
 			.with_brand(ServerBrand::Paper)
 			.with_version(BuildInfo {
 				version: String::from("1.20.1"),
+				..Default::default()
 			});
 
 		let json = serde_json::to_string(&server).unwrap();
@@ -257,6 +626,32 @@ mod tests {
 		assert_eq!(deserialized.version(), server.version());
 	}
 
+	#[test]
+	fn default_channel_skips_experimental_builds() {
+		assert!(ReleaseChannel::Default.accepts(None));
+		assert!(ReleaseChannel::Default.accepts(Some("DEFAULT")));
+		assert!(ReleaseChannel::Default.accepts(Some("stable")));
+		assert!(!ReleaseChannel::Default.accepts(Some("experimental")));
+		assert!(!ReleaseChannel::Default.accepts(Some("beta")));
+		assert!(ReleaseChannel::Experimental.accepts(Some("experimental")));
+	}
+
+	#[test]
+	fn build_info_defaults_when_missing_from_json() {
+		let info: BuildInfo = serde_json::from_str(r#"{"version":"1.21.1"}"#).unwrap();
+
+		assert!(info.build.is_none());
+		assert_eq!(info.channel, ReleaseChannel::Default);
+	}
+
+	#[test]
+	fn restart_policy_defaults_when_missing_from_json() {
+		let json = r#"{"brand":"Paper","build":{"version":"1.21.1"},"name":"Old","plugins":null}"#;
+		let server: MinecraftServer = serde_json::from_str(json).unwrap();
+
+		assert_eq!(server.restart_policy(), &RestartPolicy::Never);
+	}
+
 	#[test]
 	fn server_brand_debug() {
 		let vanilla = ServerBrand::Vanilla;
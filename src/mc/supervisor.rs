@@ -0,0 +1,1075 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::mc::console::Console;
+use crate::mc::java_runtime::JavaRegistry;
+use crate::mc::registry::ServerRegistry;
+use crate::mc::server::MinecraftServer;
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin};
+use tokio::sync::{Mutex, oneshot, watch};
+use tokio::time::timeout;
+
+const READY_MARKER: &str = "Done (";
+const KILL_TIMEOUT: Duration = Duration::from_secs(10);
+// a JVM that stopped reading its console fills the pipe, the write gives up after this.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_CRASH_RECORDS: usize = 20;
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum ServerState {
+	Stopped,
+	Starting,
+	Running,
+	Stopping,
+	Crashed,
+}
+
+impl ServerState {
+	pub fn is_active(&self) -> bool {
+		matches!(
+			self,
+			ServerState::Starting | ServerState::Running | ServerState::Stopping
+		)
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerStatus {
+	pub state: ServerState,
+	pub pid: Option<u32>,
+	pub exit_code: Option<i32>,
+	pub restarts: u32,
+}
+
+impl ServerStatus {
+	fn stopped() -> Self {
+		Self {
+			state: ServerState::Stopped,
+			pid: None,
+			exit_code: None,
+			restarts: 0,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashRecord {
+	pub exit_code: Option<i32>,
+	pub crashed_at: u64,
+	pub crash_report: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum SupervisorError {
+	#[error("Server is already running: {0}")]
+	AlreadyRunning(String),
+	#[error("Server is not running: {0}")]
+	NotRunning(String),
+	#[error("Failed to spawn server: {0}")]
+	Spawn(String),
+	#[error("EULA has not been accepted for server: {0}")]
+	EulaNotAccepted(String),
+	#[error("Server did not exit in time: {0}")]
+	Timeout(String),
+	#[error("Console error: {0}")]
+	Console(#[from] std::io::Error),
+	#[error("Server is busy with another operation: {0}")]
+	Busy(String),
+}
+
+struct ManagedProcess {
+	status: ServerStatus,
+	// its own lock, so a write blocked on a full pipe holds up only this server.
+	stdin: Option<Arc<Mutex<ChildStdin>>>,
+	kill_switch: Option<oneshot::Sender<()>>,
+	exited: watch::Receiver<bool>,
+	crashes: VecDeque<CrashRecord>,
+	restart_pending: bool,
+}
+
+impl ManagedProcess {
+	fn is_active(&self) -> bool {
+		self.status.state.is_active()
+	}
+}
+
+/// Exclusive hold on a server while its files are being replaced, released on drop.
+/// Starts through the API are refused until then.
+pub struct ServerLock {
+	name: String,
+	locked: Arc<std::sync::Mutex<HashSet<String>>>,
+}
+
+impl Drop for ServerLock {
+	fn drop(&mut self) {
+		self.locked
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.remove(&self.name);
+	}
+}
+
+/// Owns every Minecraft process Lunara spawned and tracks its lifecycle.
+#[derive(Clone, Default)]
+pub struct Supervisor {
+	processes: Arc<Mutex<HashMap<String, ManagedProcess>>>,
+	consoles: Arc<Mutex<HashMap<String, Console>>>,
+	locked: Arc<std::sync::Mutex<HashSet<String>>>,
+	// servers between the state check and the spawn, outside the processes lock.
+	starting: Arc<std::sync::Mutex<HashSet<String>>>,
+	// automatic restarts launch the record as it is now, not as it was at the first start.
+	registry: Option<ServerRegistry>,
+	// scanned on the first start, again only when a profile names a runtime it hasn't seen.
+	java: Arc<Mutex<Option<JavaRegistry>>>,
+}
+
+impl Supervisor {
+	#[cfg(test)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_registry(registry: ServerRegistry) -> Self {
+		Self {
+			registry: Some(registry),
+			..Self::default()
+		}
+	}
+
+	pub async fn status(&self, name: &str) -> ServerStatus {
+		self.processes
+			.lock()
+			.await
+			.get(name)
+			.map(|p| p.status.clone())
+			.unwrap_or_else(ServerStatus::stopped)
+	}
+
+	pub async fn crashes(&self, name: &str) -> Vec<CrashRecord> {
+		self.processes
+			.lock()
+			.await
+			.get(name)
+			.map(|p| p.crashes.iter().cloned().collect())
+			.unwrap_or_default()
+	}
+
+	/// Returns the console for a server, creating an empty one if it never ran.
+	/// Consoles outlive the process so clients stay attached across restarts.
+	pub async fn console(&self, name: &str) -> Console {
+		self.consoles
+			.lock()
+			.await
+			.entry(name.to_string())
+			.or_default()
+			.clone()
+	}
+
+	/// Locks a stopped server, only one lock per server is handed out at a time.
+	/// Taken before the state check, so a start can't slip in between.
+	pub async fn lock(&self, name: &str) -> Result<ServerLock, SupervisorError> {
+		let lock: ServerLock =
+			claim(&self.locked, name).ok_or_else(|| SupervisorError::Busy(name.to_string()))?;
+
+		if is_claimed(&self.starting, name) || self.status(name).await.state.is_active() {
+			return Err(SupervisorError::AlreadyRunning(name.to_string()));
+		}
+
+		Ok(lock)
+	}
+
+	fn is_locked(&self, name: &str) -> bool {
+		is_claimed(&self.locked, name)
+	}
+
+	async fn java_runtimes(&self, server: &MinecraftServer) -> JavaRegistry {
+		let mut java = self.java.lock().await;
+
+		let stale: bool = match (java.as_ref(), &server.launch_profile().java_path) {
+			(None, _) => true,
+			(Some(known), Some(path)) => !known.is_known_executable(Path::new(path)),
+			(Some(_), None) => false,
+		};
+
+		if stale {
+			*java = Some(JavaRegistry::discover().await);
+		}
+
+		java.clone().unwrap_or_default()
+	}
+
+	/// Spawns the server and hands the child to background watchers.
+	pub async fn start(&self, server: &MinecraftServer) -> Result<ServerStatus, SupervisorError> {
+		self.launch(server, true, None).await
+	}
+
+	/// Starts a server the caller holds the lock of.
+	pub async fn start_locked(
+		&self,
+		server: &MinecraftServer,
+		lock: &ServerLock,
+	) -> Result<ServerStatus, SupervisorError> {
+		debug_assert_eq!(lock.name, server.name());
+
+		self.launch(server, true, Some(lock)).await
+	}
+
+	/// Manual launches reset the restart counter, automatic ones keep counting.
+	/// The server is claimed while spawning so two requests can't start it twice,
+	/// without holding up every other server behind the processes lock.
+	async fn launch(
+		&self,
+		server: &MinecraftServer,
+		manual: bool,
+		held: Option<&ServerLock>,
+	) -> Result<ServerStatus, SupervisorError> {
+		let name: &str = server.name();
+
+		let _starting: ServerLock = {
+			let processes = self.processes.lock().await;
+
+			if processes.get(name).is_some_and(ManagedProcess::is_active) {
+				return Err(SupervisorError::AlreadyRunning(name.to_string()));
+			}
+
+			claim(&self.starting, name)
+				.ok_or_else(|| SupervisorError::AlreadyRunning(name.to_string()))?
+		};
+
+		// claimed first and checked second, `lock` does the opposite so one of them always loses.
+		if held.is_none() && self.is_locked(name) {
+			return Err(SupervisorError::Busy(name.to_string()));
+		}
+
+		let java: JavaRegistry = self.java_runtimes(server).await;
+		let child: Child = server.turn_on(&java).await?;
+
+		let mut processes = self.processes.lock().await;
+		Ok(self.adopt(&mut processes, server, child, manual).await)
+	}
+
+	async fn adopt(
+		&self,
+		processes: &mut HashMap<String, ManagedProcess>,
+		server: &MinecraftServer,
+		mut child: Child,
+		manual: bool,
+	) -> ServerStatus {
+		let name: String = server.name().to_string();
+		let previous: Option<ManagedProcess> = processes.remove(&name);
+
+		let restarts: u32 = match &previous {
+			Some(process) if !manual => process.status.restarts,
+			_ => 0,
+		};
+
+		let status = ServerStatus {
+			state: ServerState::Starting,
+			pid: child.id(),
+			exit_code: None,
+			restarts,
+		};
+
+		let (kill_tx, kill_rx) = oneshot::channel();
+		let (exited_tx, exited_rx) = watch::channel(false);
+
+		processes.insert(
+			name.clone(),
+			ManagedProcess {
+				status: status.clone(),
+				stdin: child.stdin.take().map(|stdin| Arc::new(Mutex::new(stdin))),
+				kill_switch: Some(kill_tx),
+				exited: exited_rx,
+				crashes: previous.map(|p| p.crashes).unwrap_or_default(),
+				restart_pending: false,
+			},
+		);
+
+		let console: Console = self.console(&name).await;
+
+		if let Some(stdout) = child.stdout.take() {
+			tokio::spawn(
+				self.clone()
+					.watch_output(name.clone(), stdout, console.clone()),
+			);
+		}
+
+		if let Some(stderr) = child.stderr.take() {
+			tokio::spawn(self.clone().watch_output(name.clone(), stderr, console));
+		}
+
+		tokio::spawn(self.clone().watch_exit(
+			server.clone(),
+			child,
+			SystemTime::now(),
+			kill_rx,
+			exited_tx,
+		));
+
+		status
+	}
+
+	/// Sends `stop` to the server console and waits for the process to exit.
+	/// A pending automatic restart counts as running, stopping it just cancels the restart.
+	pub async fn stop(&self, name: &str, limit: Duration) -> Result<ServerStatus, SupervisorError> {
+		if self.take_pending_restart(name).await {
+			return Ok(self.status(name).await);
+		}
+
+		// marked stopping before the write, so an exit that follows right away isn't a crash.
+		let (stdin, mut exited, previous) = {
+			let mut processes = self.processes.lock().await;
+			let process = active_process(&mut processes, name)?;
+			let stdin = stdin_of(process, name)?;
+			let previous: ServerState = process.status.state;
+
+			process.status.state = ServerState::Stopping;
+			(stdin, process.exited.clone(), previous)
+		};
+
+		if let Err(error) = write_line(&stdin, name, "stop").await {
+			self.transition(name, ServerState::Stopping, previous).await;
+			return Err(error);
+		}
+
+		info!("Waiting for server {} to stop", name);
+
+		timeout(limit, exited.wait_for(|done| *done))
+			.await
+			.map_err(|_| SupervisorError::Timeout(name.to_string()))?
+			.ok();
+
+		Ok(self.status(name).await)
+	}
+
+	/// Writes a command to the server's stdin as if typed into its console.
+	pub async fn send_command(&self, name: &str, command: &str) -> Result<(), SupervisorError> {
+		let stdin = {
+			let mut processes = self.processes.lock().await;
+			stdin_of(active_process(&mut processes, name)?, name)?
+		};
+
+		write_line(&stdin, name, command).await
+	}
+
+	/// Kills the process without giving the server a chance to save.
+	pub async fn kill(&self, name: &str) -> Result<ServerStatus, SupervisorError> {
+		if self.take_pending_restart(name).await {
+			return Ok(self.status(name).await);
+		}
+
+		let mut exited = {
+			let mut processes = self.processes.lock().await;
+			let process = active_process(&mut processes, name)?;
+
+			if let Some(kill_switch) = process.kill_switch.take() {
+				kill_switch.send(()).ok();
+			}
+
+			process.status.state = ServerState::Stopping;
+			process.exited.clone()
+		};
+
+		warn!("Killing server {}", name);
+
+		timeout(KILL_TIMEOUT, exited.wait_for(|done| *done))
+			.await
+			.map_err(|_| SupervisorError::Timeout(name.to_string()))?
+			.ok();
+
+		Ok(self.status(name).await)
+	}
+
+	/// Stops the server if it is active, then starts it again.
+	pub async fn restart(
+		&self,
+		server: &MinecraftServer,
+		limit: Duration,
+	) -> Result<ServerStatus, SupervisorError> {
+		if self.status(server.name()).await.state.is_active() {
+			self.stop(server.name(), limit).await?;
+		}
+
+		self.start(server).await
+	}
+
+	async fn watch_output<R: AsyncRead + Unpin>(self, name: String, output: R, console: Console) {
+		let mut lines = BufReader::new(output).lines();
+
+		while let Ok(Some(line)) = lines.next_line().await {
+			debug!("[{}] {}", name, line);
+
+			if line.contains(READY_MARKER) {
+				self.transition(&name, ServerState::Starting, ServerState::Running)
+					.await;
+			}
+
+			console.push(line);
+		}
+	}
+
+	async fn watch_exit(
+		self,
+		server: MinecraftServer,
+		mut child: Child,
+		started_at: SystemTime,
+		kill_rx: oneshot::Receiver<()>,
+		exited_tx: watch::Sender<bool>,
+	) {
+		let name: String = server.name().to_string();
+
+		let kill_requested: bool = tokio::select! {
+			_ = child.wait() => false,
+			Ok(()) = kill_rx => true,
+		};
+
+		if kill_requested && let Err(error) = child.kill().await {
+			error!("Failed to kill server {}: {}", name, error);
+		}
+
+		let exit = child.wait().await;
+
+		let crash_report: Option<String> = match &exit {
+			Ok(code) if code.success() => None,
+			_ => latest_crash_report(&server.directory(), started_at).await,
+		};
+
+		let mut processes = self.processes.lock().await;
+
+		let Some(process) = processes.get_mut(&name) else {
+			return;
+		};
+
+		let requested: bool = process.status.state == ServerState::Stopping;
+		let status = &mut process.status;
+		status.pid = None;
+		process.stdin = None;
+		process.kill_switch = None;
+
+		let crashed: bool = match exit {
+			Ok(code) if code.success() || requested => {
+				info!("Server {} stopped", name);
+
+				status.state = ServerState::Stopped;
+				status.exit_code = code.code();
+				false
+			}
+			Ok(code) => {
+				warn!("Server {} crashed with {}", name, code);
+
+				status.state = ServerState::Crashed;
+				status.exit_code = code.code();
+				true
+			}
+			Err(error) => {
+				error!("Lost track of server {}: {}", name, error);
+
+				status.state = ServerState::Crashed;
+				true
+			}
+		};
+
+		if crashed {
+			if process.crashes.len() == MAX_CRASH_RECORDS {
+				process.crashes.pop_front();
+			}
+
+			process.crashes.push_back(CrashRecord {
+				exit_code: process.status.exit_code,
+				crashed_at: unix_now(),
+				crash_report,
+			});
+		}
+
+		let delay: Option<Duration> = match requested {
+			true => None,
+			false => server
+				.restart_policy()
+				.delay(process.status.restarts, crashed),
+		};
+
+		if delay.is_some() {
+			process.status.restarts += 1;
+			process.restart_pending = true;
+		}
+
+		drop(processes);
+		exited_tx.send(true).ok();
+
+		if let Some(delay) = delay {
+			self.schedule_restart(server, delay).await;
+		}
+	}
+
+	// boxed so the restart future doesn't have to name watch_exit's own type.
+	fn schedule_restart(
+		self,
+		server: MinecraftServer,
+		delay: Duration,
+	) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+		Box::pin(async move {
+			info!("Restarting server {} in {:?}", server.name(), delay);
+
+			tokio::time::sleep(delay).await;
+
+			if !self.take_pending_restart(server.name()).await {
+				info!("Restart of server {} was cancelled", server.name());
+				return;
+			}
+
+			if self.is_locked(server.name()) {
+				info!("Server {} is locked, skipping its restart", server.name());
+				return;
+			}
+
+			let Some(server) = self.current(server).await else {
+				return;
+			};
+
+			if let Err(error) = self.launch(&server, false, None).await {
+				error!("Automatic restart of {} failed: {}", server.name(), error);
+			}
+		})
+	}
+
+	/// The stored record of `server`, picking up edits made since it was started.
+	/// `None` when it was deleted meanwhile.
+	async fn current(&self, server: MinecraftServer) -> Option<MinecraftServer> {
+		let Some(registry) = &self.registry else {
+			return Some(server);
+		};
+
+		match registry.get(server.name()).await {
+			Ok(current) => Some(current),
+			Err(error) => {
+				warn!(
+					"Not restarting {}, cannot load its record: {}",
+					server.name(),
+					error
+				);
+				None
+			}
+		}
+	}
+
+	async fn take_pending_restart(&self, name: &str) -> bool {
+		let mut processes = self.processes.lock().await;
+
+		match processes.get_mut(name) {
+			Some(process) if process.restart_pending => {
+				process.restart_pending = false;
+				true
+			}
+			_ => false,
+		}
+	}
+
+	async fn transition(&self, name: &str, from: ServerState, to: ServerState) {
+		if let Some(process) = self.processes.lock().await.get_mut(name)
+			&& process.status.state == from
+		{
+			process.status.state = to;
+		}
+	}
+}
+
+/// Finds the newest file in `crash-reports/` written since the process started.
+async fn latest_crash_report(directory: &str, since: SystemTime) -> Option<String> {
+	let mut entries = tokio::fs::read_dir(format!("{}/crash-reports", directory))
+		.await
+		.ok()?;
+
+	let mut latest: Option<(SystemTime, PathBuf)> = None;
+
+	while let Ok(Some(entry)) = entries.next_entry().await {
+		let Ok(modified) = entry.metadata().await.and_then(|m| m.modified()) else {
+			continue;
+		};
+
+		if modified < since || latest.as_ref().is_some_and(|(time, _)| *time >= modified) {
+			continue;
+		}
+
+		latest = Some((modified, entry.path()));
+	}
+
+	latest.map(|(_, path)| path.display().to_string())
+}
+
+fn unix_now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or_default()
+}
+
+fn stdin_of(
+	process: &ManagedProcess,
+	name: &str,
+) -> Result<Arc<Mutex<ChildStdin>>, SupervisorError> {
+	process
+		.stdin
+		.clone()
+		.ok_or_else(|| SupervisorError::NotRunning(name.to_string()))
+}
+
+/// Called without the processes lock held, only this server waits on a stuck pipe.
+async fn write_line(
+	stdin: &Mutex<ChildStdin>,
+	name: &str,
+	line: &str,
+) -> Result<(), SupervisorError> {
+	let write = async {
+		let mut stdin = stdin.lock().await;
+
+		stdin.write_all(format!("{}\n", line).as_bytes()).await?;
+		stdin.flush().await
+	};
+
+	timeout(WRITE_TIMEOUT, write)
+		.await
+		.map_err(|_| SupervisorError::Timeout(name.to_string()))??;
+
+	Ok(())
+}
+
+fn active_process<'a>(
+	processes: &'a mut HashMap<String, ManagedProcess>,
+	name: &str,
+) -> Result<&'a mut ManagedProcess, SupervisorError> {
+	processes
+		.get_mut(name)
+		.filter(|p| p.is_active())
+		.ok_or_else(|| SupervisorError::NotRunning(name.to_string()))
+}
+
+/// Adds `name` to `set` until the returned guard drops, `None` if it is already there.
+fn claim(set: &Arc<std::sync::Mutex<HashSet<String>>>, name: &str) -> Option<ServerLock> {
+	set.lock()
+		.unwrap_or_else(PoisonError::into_inner)
+		.insert(name.to_string())
+		.then(|| ServerLock {
+			name: name.to_string(),
+			locked: set.clone(),
+		})
+}
+
+fn is_claimed(set: &std::sync::Mutex<HashSet<String>>, name: &str) -> bool {
+	set.lock()
+		.unwrap_or_else(PoisonError::into_inner)
+		.contains(name)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mc::restart_policy::RestartPolicy;
+	use std::process::Stdio;
+	use tokio::process::Command;
+
+	async fn adopt_shell(supervisor: &Supervisor, name: &str, script: &str) -> ServerStatus {
+		let mut server = MinecraftServer::new();
+		server.with_name(Some(name.to_string()));
+
+		adopt_server(supervisor, &server, script).await
+	}
+
+	async fn adopt_server(
+		supervisor: &Supervisor,
+		server: &MinecraftServer,
+		script: &str,
+	) -> ServerStatus {
+		let child: Child = Command::new("sh")
+			.arg("-c")
+			.arg(script)
+			.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()
+			.unwrap();
+
+		let mut processes = supervisor.processes.lock().await;
+		supervisor.adopt(&mut processes, server, child, true).await
+	}
+
+	#[tokio::test]
+	async fn unknown_server_is_stopped() {
+		let supervisor = Supervisor::new();
+		let status = supervisor.status("ghost").await;
+
+		assert_eq!(status.state, ServerState::Stopped);
+		assert!(status.pid.is_none());
+	}
+
+	#[tokio::test]
+	async fn refuses_to_start_active_server() {
+		let supervisor = Supervisor::new();
+		let mut server = MinecraftServer::new();
+		server.with_name(Some(String::from("busy")));
+
+		adopt_shell(&supervisor, "busy", "sleep 30").await;
+
+		let result = supervisor.start(&server).await;
+
+		assert!(matches!(result, Err(SupervisorError::AlreadyRunning(_))));
+
+		supervisor.kill("busy").await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn locked_server_only_starts_through_its_lock() {
+		let supervisor = Supervisor::new();
+		let mut server = MinecraftServer::new();
+		server.with_name(Some(String::from("locked")));
+
+		let lock = supervisor.lock("locked").await.unwrap();
+
+		assert!(matches!(
+			supervisor.lock("locked").await,
+			Err(SupervisorError::Busy(_))
+		));
+		assert!(matches!(
+			supervisor.start(&server).await,
+			Err(SupervisorError::Busy(_))
+		));
+
+		drop(lock);
+		let relocked = supervisor.lock("locked").await;
+		assert!(relocked.is_ok());
+	}
+
+	#[tokio::test]
+	async fn running_server_cannot_be_locked() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(&supervisor, "up", "sleep 30").await;
+
+		assert!(matches!(
+			supervisor.lock("up").await,
+			Err(SupervisorError::AlreadyRunning(_))
+		));
+		// the failed attempt doesn't leave the server locked.
+		assert!(!supervisor.is_locked("up"));
+
+		supervisor.kill("up").await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn server_being_started_cannot_be_locked_or_started_again() {
+		let supervisor = Supervisor::new();
+		let mut server = MinecraftServer::new();
+		server.with_name(Some(String::from("starting")));
+
+		let starting = claim(&supervisor.starting, "starting").unwrap();
+
+		assert!(matches!(
+			supervisor.lock("starting").await,
+			Err(SupervisorError::AlreadyRunning(_))
+		));
+		assert!(matches!(
+			supervisor.start(&server).await,
+			Err(SupervisorError::AlreadyRunning(_))
+		));
+		// other servers aren't held up meanwhile.
+		assert_eq!(supervisor.status("other").await.state, ServerState::Stopped);
+
+		drop(starting);
+		assert!(supervisor.lock("starting").await.is_ok());
+	}
+
+	#[tokio::test]
+	async fn output_marker_marks_server_running() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(&supervisor, "ready", "echo 'Done (1.0s)!'; sleep 30").await;
+
+		tokio::time::sleep(Duration::from_millis(200)).await;
+
+		assert_eq!(supervisor.status("ready").await.state, ServerState::Running);
+
+		supervisor.kill("ready").await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn stop_writes_to_console_and_waits() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(
+			&supervisor,
+			"graceful",
+			"read line; [ \"$line\" = stop ] && exit 0; exit 1",
+		)
+		.await;
+
+		let status = supervisor
+			.stop("graceful", Duration::from_secs(5))
+			.await
+			.unwrap();
+
+		assert_eq!(status.state, ServerState::Stopped);
+		assert_eq!(status.exit_code, Some(0));
+	}
+
+	#[tokio::test]
+	async fn stop_times_out_when_server_ignores_console() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(&supervisor, "stubborn", "sleep 30").await;
+
+		let result = supervisor
+			.stop("stubborn", Duration::from_millis(200))
+			.await;
+
+		assert!(matches!(result, Err(SupervisorError::Timeout(_))));
+
+		supervisor.kill("stubborn").await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn full_console_pipe_does_not_block_other_servers() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(&supervisor, "deaf", "sleep 30").await;
+
+		// more than a pipe holds, and nothing on the other end reads it.
+		let flood = supervisor.clone();
+		let blocked =
+			tokio::spawn(async move { flood.send_command("deaf", &"x".repeat(1 << 20)).await });
+
+		tokio::time::sleep(Duration::from_millis(200)).await;
+
+		let status = timeout(Duration::from_secs(1), supervisor.status("deaf")).await;
+		assert_eq!(status.unwrap().state, ServerState::Starting);
+
+		supervisor.kill("deaf").await.unwrap();
+		assert!(blocked.await.unwrap().is_err());
+	}
+
+	#[tokio::test]
+	async fn kill_is_not_recorded_as_crash() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(&supervisor, "doomed", "sleep 30").await;
+
+		let status = supervisor.kill("doomed").await.unwrap();
+
+		assert_eq!(status.state, ServerState::Stopped);
+		assert!(status.pid.is_none());
+	}
+
+	#[tokio::test]
+	async fn non_zero_exit_is_a_crash() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(&supervisor, "broken", "exit 3").await;
+
+		tokio::time::sleep(Duration::from_millis(200)).await;
+
+		let status = supervisor.status("broken").await;
+
+		assert_eq!(status.state, ServerState::Crashed);
+		assert_eq!(status.exit_code, Some(3));
+	}
+
+	#[tokio::test]
+	async fn console_receives_output_and_commands() {
+		let supervisor = Supervisor::new();
+		let (_, mut lines) = supervisor.console("echo").await.subscribe();
+
+		adopt_shell(
+			&supervisor,
+			"echo",
+			"echo booting; echo oops >&2; read line; echo \"got $line\"",
+		)
+		.await;
+
+		supervisor.send_command("echo", "say hi").await.unwrap();
+
+		let mut received = vec![];
+		while received.len() < 3 {
+			received.push(lines.recv().await.unwrap());
+		}
+
+		assert!(received.contains(&String::from("booting")));
+		assert!(received.contains(&String::from("oops")));
+		assert!(received.contains(&String::from("got say hi")));
+	}
+
+	#[tokio::test]
+	async fn console_keeps_scrollback_after_exit() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(&supervisor, "gone", "echo last words").await;
+
+		tokio::time::sleep(Duration::from_millis(200)).await;
+
+		let (history, _) = supervisor.console("gone").await.subscribe();
+
+		assert_eq!(history, vec!["last words"]);
+	}
+
+	#[tokio::test]
+	async fn crash_is_recorded() {
+		let supervisor = Supervisor::new();
+
+		adopt_shell(&supervisor, "fragile", "exit 7").await;
+
+		tokio::time::sleep(Duration::from_millis(200)).await;
+
+		let crashes = supervisor.crashes("fragile").await;
+
+		assert_eq!(crashes.len(), 1);
+		assert_eq!(crashes[0].exit_code, Some(7));
+		assert!(crashes[0].crash_report.is_none());
+	}
+
+	#[tokio::test]
+	async fn crash_schedules_restart_that_stop_cancels() {
+		let supervisor = Supervisor::new();
+		let mut server = MinecraftServer::new();
+		server
+			.with_name(Some(String::from("flaky")))
+			.with_restart_policy(RestartPolicy::OnFailure {
+				max_retries: 2,
+				backoff_secs: 30,
+			});
+
+		adopt_server(&supervisor, &server, "exit 1").await;
+
+		tokio::time::sleep(Duration::from_millis(200)).await;
+
+		let status = supervisor.status("flaky").await;
+		assert_eq!(status.state, ServerState::Crashed);
+		assert_eq!(status.restarts, 1);
+
+		supervisor.stop("flaky", STOP_TIMEOUT).await.unwrap();
+
+		assert!(!supervisor.take_pending_restart("flaky").await);
+	}
+
+	#[tokio::test]
+	async fn restarts_use_the_stored_record() {
+		let registry = ServerRegistry::new(std::env::temp_dir().join(format!(
+			"lunara-supervisor-registry-{}.json",
+			std::process::id()
+		)));
+		let supervisor = Supervisor::with_registry(registry.clone());
+
+		let mut server = MinecraftServer::new();
+		server.with_name(Some(String::from("edited")));
+		registry.insert(server.clone()).await.unwrap();
+
+		registry
+			.update("edited", |s| {
+				s.with_restart_policy(RestartPolicy::Always { backoff_secs: 5 });
+			})
+			.await
+			.unwrap();
+
+		let current = supervisor.current(server.clone()).await.unwrap();
+		assert_eq!(
+			current.restart_policy(),
+			&RestartPolicy::Always { backoff_secs: 5 }
+		);
+
+		registry.remove("edited").await.unwrap();
+		assert!(supervisor.current(server.clone()).await.is_none());
+
+		// without a registry the captured copy is all there is.
+		assert_eq!(
+			Supervisor::new().current(server).await.unwrap().name(),
+			"edited"
+		);
+	}
+
+	#[tokio::test]
+	async fn requested_stop_is_not_restarted() {
+		let supervisor = Supervisor::new();
+		let mut server = MinecraftServer::new();
+		server
+			.with_name(Some(String::from("steady")))
+			.with_restart_policy(RestartPolicy::Always { backoff_secs: 30 });
+
+		adopt_server(&supervisor, &server, "sleep 30").await;
+
+		let status = supervisor.kill("steady").await.unwrap();
+
+		assert_eq!(status.state, ServerState::Stopped);
+		assert_eq!(status.restarts, 0);
+		assert!(!supervisor.take_pending_restart("steady").await);
+	}
+
+	#[tokio::test]
+	async fn finds_newest_crash_report() {
+		let directory = std::env::temp_dir().join(format!("lunara-crash-{}", std::process::id()));
+		let reports = directory.join("crash-reports");
+		tokio::fs::create_dir_all(&reports).await.unwrap();
+
+		let since = SystemTime::now() - Duration::from_secs(60);
+		tokio::fs::write(reports.join("crash-old.txt"), "old")
+			.await
+			.unwrap();
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		tokio::fs::write(reports.join("crash-new.txt"), "new")
+			.await
+			.unwrap();
+
+		let found = latest_crash_report(directory.to_str().unwrap(), since).await;
+		let none_since = latest_crash_report(
+			directory.to_str().unwrap(),
+			SystemTime::now() + Duration::from_secs(60),
+		)
+		.await;
+
+		tokio::fs::remove_dir_all(&directory).await.ok();
+
+		assert!(found.unwrap().ends_with("crash-new.txt"));
+		assert!(none_since.is_none());
+	}
+
+	#[tokio::test]
+	async fn stop_and_kill_require_active_server() {
+		let supervisor = Supervisor::new();
+
+		let stop = supervisor.stop("ghost", STOP_TIMEOUT).await;
+		let kill = supervisor.kill("ghost").await;
+		let command = supervisor.send_command("ghost", "list").await;
+
+		assert!(matches!(stop, Err(SupervisorError::NotRunning(_))));
+		assert!(matches!(kill, Err(SupervisorError::NotRunning(_))));
+		assert!(matches!(command, Err(SupervisorError::NotRunning(_))));
+	}
+
+	#[test]
+	fn active_states() {
+		assert!(ServerState::Starting.is_active());
+		assert!(ServerState::Running.is_active());
+		assert!(ServerState::Stopping.is_active());
+		assert!(!ServerState::Stopped.is_active());
+		assert!(!ServerState::Crashed.is_active());
+	}
+}
@@ -0,0 +1,526 @@
+/*
+Copyright 2026 seasnail1
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+	http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+
+*/
+use crate::http::artifact_cache::ArtifactCache;
+use crate::http::download::DownloadProgress;
+use crate::http::server_creator::{HttpClientError, Installed, ServerCreator};
+use crate::mc::launch_profile::LaunchTarget;
+use crate::mc::server::{BuildInfo, MinecraftServer};
+use crate::mc::supervisor::{STOP_TIMEOUT, ServerLock, ServerState, Supervisor, SupervisorError};
+use log::{error, info, warn};
+use reqwest::Client;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::fs::{
+	copy, create_dir_all, read_dir, remove_dir_all, remove_file, rename, symlink_metadata,
+};
+use tokio::sync::watch;
+
+// inside the server root, server names can't start with a dot so it never clashes.
+pub(crate) const SNAPSHOT_DIR: &str = ".snapshots";
+const STAGING_DIR: &str = ".upgrade";
+const BOOT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const BOOT_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub enum UpgradeError {
+	#[error("Supervisor error: {0}")]
+	Supervisor(#[from] SupervisorError),
+	#[error("Download failed: {0}")]
+	Download(#[from] HttpClientError),
+	#[error("File error: {0}")]
+	Io(#[from] std::io::Error),
+	#[error("First boot failed, restored the snapshot: {0}")]
+	RolledBack(String),
+}
+
+/// Copies of the parts of a server an upgrade can break, kept until the next upgrade.
+pub(crate) struct Snapshot {
+	root: PathBuf,
+	// entries that didn't exist are removed again on restore.
+	entries: Vec<(String, bool)>,
+}
+
+impl Snapshot {
+	pub(crate) async fn take(
+		server_dir: &Path,
+		root: &Path,
+		entries: &[String],
+	) -> std::io::Result<Self> {
+		remove_path(root).await?;
+		create_dir_all(root).await?;
+
+		let mut taken: Vec<(String, bool)> = Vec::new();
+
+		for entry in entries {
+			let source: PathBuf = server_dir.join(entry);
+			let exists: bool = symlink_metadata(&source).await.is_ok();
+
+			if exists {
+				copy_tree(&source, &root.join(entry)).await?;
+			}
+
+			taken.push((entry.clone(), exists));
+		}
+
+		Ok(Self {
+			root: root.to_path_buf(),
+			entries: taken,
+		})
+	}
+
+	pub(crate) async fn restore(&self, server_dir: &Path) -> std::io::Result<()> {
+		for (entry, existed) in &self.entries {
+			let target: PathBuf = server_dir.join(entry);
+
+			remove_path(&target).await?;
+
+			if *existed {
+				copy_tree(&self.root.join(entry), &target).await?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl MinecraftServer {
+	/// Moves a stopped server to another version or build in place and returns the updated server.
+	/// Everything the upgrade replaces is snapshotted under `snapshot_root` first and restored
+	/// if the first boot crashes.
+	pub(crate) async fn upgrade(
+		&self,
+		supervisor: &Supervisor,
+		lock: &ServerLock,
+		cache: &ArtifactCache,
+		snapshot_root: &Path,
+		target: BuildInfo,
+		progress: Option<&watch::Sender<DownloadProgress>>,
+	) -> Result<MinecraftServer, UpgradeError> {
+		let directory: PathBuf = PathBuf::from(self.directory());
+
+		// cancels a pending automatic restart.
+		match self.stop(supervisor, STOP_TIMEOUT).await {
+			Ok(_) | Err(SupervisorError::NotRunning(_)) => {}
+			Err(error) => return Err(error.into()),
+		}
+
+		let mut upgraded: MinecraftServer = self.clone();
+		upgraded.with_version(target);
+
+		let staging: PathBuf = directory.join(STAGING_DIR);
+		remove_path(&staging).await?;
+		create_dir_all(&staging).await?;
+
+		let installed: Installed = match upgraded
+			.try_download(cache, &staging.to_string_lossy(), progress)
+			.await
+		{
+			Ok(installed) => installed,
+			Err(error) => {
+				remove_path(&staging).await.ok();
+				return Err(error.into());
+			}
+		};
+
+		let snapshot = match self.snapshot_entries(&staging).await {
+			Ok(entries) => {
+				Snapshot::take(&directory, &snapshot_root.join(self.name()), &entries).await
+			}
+			Err(error) => Err(error),
+		};
+
+		let snapshot: Snapshot = match snapshot {
+			Ok(snapshot) => snapshot,
+			Err(error) => {
+				remove_path(&staging).await.ok();
+				return Err(error.into());
+			}
+		};
+
+		info!(
+			"Snapshot of {} taken, upgrading to {}",
+			self.name(),
+			upgraded.version()
+		);
+
+		// nothing in the server directory changed until here.
+		match swap_in(&staging, &directory, self.name(), installed.launch).await {
+			Ok(launch) => {
+				upgraded.with_launch_target(launch);
+			}
+			Err(error) => {
+				error!(
+					"Could not swap in the upgrade of {}: {}",
+					self.name(),
+					error
+				);
+
+				snapshot.restore(&directory).await?;
+				remove_path(&staging).await.ok();
+
+				return Err(error.into());
+			}
+		}
+
+		upgraded.with_build_id(installed.build);
+
+		match upgraded.resolve_java_major(&Client::new()).await {
+			Ok(major) => {
+				upgraded.with_java_major(major);
+			}
+			Err(error) => warn!("Could not look up the required Java version: {}", error),
+		}
+
+		if !upgraded.eula_accepted().await {
+			warn!(
+				"EULA not accepted for {}, skipping the first boot check",
+				self.name()
+			);
+			return Ok(upgraded);
+		}
+
+		match first_boot(supervisor, lock, &upgraded).await {
+			Ok(()) => {
+				upgraded.stop(supervisor, STOP_TIMEOUT).await?;
+
+				info!("Upgraded {} to {}", self.name(), upgraded.version());
+				Ok(upgraded)
+			}
+			Err(reason) => {
+				error!("First boot of upgraded {} failed: {}", self.name(), reason);
+
+				if let Err(error) = upgraded.stop(supervisor, STOP_TIMEOUT).await {
+					warn!(
+						"Could not stop {} before rolling back: {}",
+						self.name(),
+						error
+					);
+				}
+
+				snapshot.restore(&directory).await?;
+
+				Err(UpgradeError::RolledBack(reason))
+			}
+		}
+	}
+
+	/// The worlds, plugin and mod folders, the jar Lunara renamed at creation,
+	/// and everything at the top of `staging` the merge is about to overwrite.
+	async fn snapshot_entries(&self, staging: &Path) -> std::io::Result<Vec<String>> {
+		let level: String = self
+			.properties()
+			.await
+			.ok()
+			.and_then(|p| p.get("level-name").cloned())
+			.filter(|level| !level.is_empty())
+			.unwrap_or_else(|| String::from("world"));
+
+		let mut entries: Vec<String> = vec![
+			format!("{}_nether", level),
+			format!("{}_the_end", level),
+			level,
+			String::from("plugins"),
+			String::from("mods"),
+			format!("{}.jar", self.name()),
+		];
+
+		for staged in top_level_entries(staging).await? {
+			if !entries.contains(&staged) {
+				entries.push(staged);
+			}
+		}
+
+		Ok(entries)
+	}
+}
+
+/// Merges the staged install over the server and returns the launch target to record.
+async fn swap_in(
+	staging: &Path,
+	directory: &Path,
+	name: &str,
+	launch: LaunchTarget,
+) -> std::io::Result<Option<LaunchTarget>> {
+	merge_tree(staging, directory).await?;
+
+	let launch: Option<LaunchTarget> = match launch {
+		// legacy Forge leaves the vanilla jar and libraries next to the launch jar.
+		LaunchTarget::Jar(jar_name) => {
+			let jar: String = format!("{}.jar", name);
+
+			if jar_name != jar {
+				rename(directory.join(jar_name), directory.join(jar)).await?;
+			}
+
+			None
+		}
+		launch => Some(launch),
+	};
+
+	remove_path(staging).await?;
+	Ok(launch)
+}
+
+/// Starts the server and waits until it is done loading or exits.
+async fn first_boot(
+	supervisor: &Supervisor,
+	lock: &ServerLock,
+	server: &MinecraftServer,
+) -> Result<(), String> {
+	supervisor
+		.start_locked(server, lock)
+		.await
+		.map_err(|e| e.to_string())?;
+
+	let started: Instant = Instant::now();
+
+	loop {
+		let status = supervisor.status(server.name()).await;
+
+		match status.state {
+			ServerState::Running => return Ok(()),
+			ServerState::Crashed | ServerState::Stopped => {
+				return Err(format!("server exited with code {:?}", status.exit_code));
+			}
+			_ if started.elapsed() > BOOT_TIMEOUT => {
+				supervisor.kill(server.name()).await.ok();
+
+				return Err(format!(
+					"server did not finish loading in {:?}",
+					BOOT_TIMEOUT
+				));
+			}
+			_ => tokio::time::sleep(BOOT_POLL).await,
+		}
+	}
+}
+
+async fn top_level_entries(dir: &Path) -> std::io::Result<Vec<String>> {
+	let mut names: Vec<String> = Vec::new();
+	let mut entries = read_dir(dir).await?;
+
+	while let Some(entry) = entries.next_entry().await? {
+		names.push(entry.file_name().to_string_lossy().into_owned());
+	}
+
+	names.sort();
+	Ok(names)
+}
+
+async fn remove_path(path: &Path) -> std::io::Result<()> {
+	match symlink_metadata(path).await {
+		Ok(metadata) if metadata.is_dir() => remove_dir_all(path).await,
+		Ok(_) => remove_file(path).await,
+		Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+		Err(e) => Err(e),
+	}
+}
+
+async fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+	if !symlink_metadata(from).await?.is_dir() {
+		if let Some(parent) = to.parent() {
+			create_dir_all(parent).await?;
+		}
+
+		copy(from, to).await?;
+		return Ok(());
+	}
+
+	let mut pending: Vec<(PathBuf, PathBuf)> = vec![(from.to_path_buf(), to.to_path_buf())];
+
+	while let Some((from, to)) = pending.pop() {
+		create_dir_all(&to).await?;
+
+		let mut entries = read_dir(&from).await?;
+
+		while let Some(entry) = entries.next_entry().await? {
+			let target: PathBuf = to.join(entry.file_name());
+
+			if entry.file_type().await?.is_dir() {
+				pending.push((entry.path(), target));
+			} else {
+				copy(entry.path(), target).await?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Moves everything under `from` into `to`, replacing files that are already there.
+async fn merge_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+	let mut pending: Vec<(PathBuf, PathBuf)> = vec![(from.to_path_buf(), to.to_path_buf())];
+
+	while let Some((from, to)) = pending.pop() {
+		create_dir_all(&to).await?;
+
+		let mut entries = read_dir(&from).await?;
+
+		while let Some(entry) = entries.next_entry().await? {
+			let target: PathBuf = to.join(entry.file_name());
+			let target_is_dir: bool = symlink_metadata(&target)
+				.await
+				.is_ok_and(|metadata| metadata.is_dir());
+
+			if entry.file_type().await?.is_dir() && target_is_dir {
+				pending.push((entry.path(), target));
+			} else {
+				remove_path(&target).await?;
+				rename(entry.path(), target).await?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::fs::{read_to_string, write};
+
+	fn temp_root(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("lunara-upgrade-{}-{}", name, std::process::id()))
+	}
+
+	#[tokio::test]
+	async fn restore_undoes_changes_to_snapshotted_entries() {
+		let root = temp_root("restore");
+		let server = root.join("server");
+		create_dir_all(server.join("world/region")).await.unwrap();
+		write(server.join("world/region/r.0.0.mca"), "old")
+			.await
+			.unwrap();
+		write(server.join("lobby.jar"), "1.20.4").await.unwrap();
+
+		let entries = [
+			String::from("world"),
+			String::from("world_nether"),
+			String::from("lobby.jar"),
+		];
+		let snapshot = Snapshot::take(&server, &root.join("snapshot"), &entries)
+			.await
+			.unwrap();
+
+		write(server.join("world/region/r.0.0.mca"), "new")
+			.await
+			.unwrap();
+		write(server.join("world/level.dat"), "new").await.unwrap();
+		create_dir_all(server.join("world_nether")).await.unwrap();
+		write(server.join("lobby.jar"), "1.21.1").await.unwrap();
+
+		snapshot.restore(&server).await.unwrap();
+
+		assert_eq!(
+			read_to_string(server.join("world/region/r.0.0.mca"))
+				.await
+				.unwrap(),
+			"old"
+		);
+		assert!(!server.join("world/level.dat").exists());
+		assert!(!server.join("world_nether").exists());
+		assert_eq!(
+			read_to_string(server.join("lobby.jar")).await.unwrap(),
+			"1.20.4"
+		);
+
+		remove_dir_all(root).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn merge_replaces_files_and_keeps_the_rest() {
+		let root = temp_root("merge");
+		let staging = root.join("staging");
+		let server = root.join("server");
+		create_dir_all(staging.join("libraries/new")).await.unwrap();
+		create_dir_all(server.join("libraries/old")).await.unwrap();
+		write(staging.join("libraries/new/lib.jar"), "new")
+			.await
+			.unwrap();
+		write(staging.join("run.sh"), "new").await.unwrap();
+		write(server.join("libraries/old/lib.jar"), "old")
+			.await
+			.unwrap();
+		write(server.join("run.sh"), "old").await.unwrap();
+
+		merge_tree(&staging, &server).await.unwrap();
+
+		assert!(server.join("libraries/new/lib.jar").exists());
+		assert!(server.join("libraries/old/lib.jar").exists());
+		assert_eq!(read_to_string(server.join("run.sh")).await.unwrap(), "new");
+
+		remove_dir_all(root).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn snapshot_of_staged_entries_undoes_a_merge() {
+		let root = temp_root("staged");
+		let staging = root.join("staging");
+		let server = root.join("server");
+		create_dir_all(staging.join("libraries/new")).await.unwrap();
+		create_dir_all(server.join("libraries/old")).await.unwrap();
+		write(staging.join("libraries/new/lib.jar"), "new")
+			.await
+			.unwrap();
+		write(staging.join("run.sh"), "new").await.unwrap();
+		write(server.join("libraries/old/lib.jar"), "old")
+			.await
+			.unwrap();
+
+		let entries = top_level_entries(&staging).await.unwrap();
+		assert_eq!(entries, ["libraries", "run.sh"]);
+
+		let snapshot = Snapshot::take(&server, &root.join("snapshot"), &entries)
+			.await
+			.unwrap();
+		merge_tree(&staging, &server).await.unwrap();
+		snapshot.restore(&server).await.unwrap();
+
+		assert!(server.join("libraries/old/lib.jar").exists());
+		assert!(!server.join("libraries/new").exists());
+		assert!(!server.join("run.sh").exists());
+
+		remove_dir_all(root).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn failed_swap_leaves_a_merge_to_undo() {
+		let root = temp_root("swap");
+		let staging = root.join("staging");
+		let server = root.join("server");
+		create_dir_all(&staging).await.unwrap();
+		create_dir_all(&server).await.unwrap();
+		write(staging.join("run.sh"), "new").await.unwrap();
+		write(server.join("run.sh"), "old").await.unwrap();
+
+		let entries = top_level_entries(&staging).await.unwrap();
+		let snapshot = Snapshot::take(&server, &root.join("snapshot"), &entries)
+			.await
+			.unwrap();
+
+		// the launch jar the installer reported is not there.
+		let launch = LaunchTarget::Jar(String::from("missing.jar"));
+		assert!(swap_in(&staging, &server, "lobby", launch).await.is_err());
+
+		snapshot.restore(&server).await.unwrap();
+		assert_eq!(read_to_string(server.join("run.sh")).await.unwrap(), "old");
+
+		remove_dir_all(root).await.unwrap();
+	}
+}
@@ -14,77 +14,291 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
-use crate::http::plugin_fetch::{TrendingPlugin, fetch_plugin_versions, fetch_trending_plugins};
-use crate::http::server_creator::ServerCreator;
-use crate::mc::plugin::Plugin;
-use crate::mc::server::ServerBrand::Vanilla;
-use crate::mc::server::{BuildInfo, MinecraftServer, ServerBrand};
+use crate::http::artifact_cache::ArtifactCache;
+use crate::http::download::DownloadProgress;
+use crate::http::plugin_fetch::{PluginError, TrendingPlugin, fetch_trending_plugins};
+use crate::http::plugin_source::{
+	PluginSearch, PluginSort, PluginSource, PluginVersion, SearchPage,
+};
+use crate::http::server_creator::{HttpClientError, Installed, ServerCreator};
+use crate::http::server_provider::{CatalogVersion, ServerProvider};
+use crate::http::version_catalog::{CATALOG_TTL, VersionBuilds, VersionCatalog};
+use crate::keyring_service::{KeyringService, SERVICE_NAME};
+use crate::mc::console::Console;
+use crate::mc::dependencies::PluginInstall;
+use crate::mc::descriptor::{InstalledJar, scan};
+use crate::mc::java_runtime::{JavaRegistry, JavaRuntime};
+use crate::mc::jobs::{JobStatus, Jobs};
+use crate::mc::launch_profile::{LaunchProfile, LaunchTarget};
+use crate::mc::ping::ServerPing;
+use crate::mc::plugin::{Plugin, PluginOrigin};
+use crate::mc::registry::{RegistryError, ServerRegistry};
+use crate::mc::restart_policy::RestartPolicy;
+use crate::mc::server::{
+	BuildInfo, EULA_URL, MinecraftServer, ReleaseChannel, ServerBrand, is_plugin_file_name,
+};
+use crate::mc::supervisor::{
+	CrashRecord, STOP_TIMEOUT, ServerLock, ServerStatus, Supervisor, SupervisorError,
+};
+use crate::mc::upgrade::SNAPSHOT_DIR;
 use crate::route::route_error::RouteError;
-use crate::route::route_error::RouteError::{InternalError, NotFound};
+use crate::route::route_error::RouteError::{
+	BadRequest, Conflict, EulaNotAccepted, InternalError, NotFound,
+};
 use axum::Json;
 use axum::Router;
-use axum::extract::{Path, Query};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{FromRef, Path, Query, State};
 use axum::http::StatusCode;
+use axum::response::Response;
 use axum::routing::get;
-use core::str::from_utf8;
+use futures_util::future::join_all;
+use futures_util::{SinkExt, StreamExt};
 use log::{debug, error, info, warn};
 use reqwest::Client;
-use serde::Deserialize;
-use tokio::fs::{File, create_dir_all};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-
-const CONF_LOCATION: &str = "servers.json";
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs::{create_dir, create_dir_all, remove_dir_all, rename};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_BACKOFF_SECS: u64 = 5;
 
 #[derive(Deserialize)]
 struct TrendingQuery {
 	trending: Option<usize>,
 }
 
-async fn load_server_by_name(name: &str) -> Result<MinecraftServer, RouteError> {
-	let path = std::path::Path::new(CONF_LOCATION);
-	let mut file = File::open(path)
-		.await
-		.map_err(|e| InternalError(format!("failed to open servers.json: {e}")))?;
+#[derive(Deserialize)]
+struct EulaQuery {
+	#[serde(default)]
+	accept_eula: bool,
+}
 
-	let mut contents = vec![];
-	file.read_to_end(&mut contents)
-		.await
-		.map_err(|e| InternalError(format!("failed to read servers.json: {e}")))?;
+#[derive(Deserialize)]
+struct CreateQuery {
+	#[serde(default)]
+	accept_eula: bool,
+	build: Option<String>,
+	#[serde(default)]
+	channel: ReleaseChannel,
+}
+
+impl CreateQuery {
+	fn build_info(self, version: String) -> BuildInfo {
+		BuildInfo {
+			version,
+			build: self.build,
+			channel: self.channel,
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct UpgradeQuery {
+	build: Option<String>,
+	#[serde(default)]
+	channel: ReleaseChannel,
+}
+
+impl UpgradeQuery {
+	fn build_info(self, version: String) -> BuildInfo {
+		BuildInfo {
+			version,
+			build: self.build,
+			channel: self.channel,
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct PluginSearchQuery {
+	#[serde(default)]
+	source: PluginOrigin,
+	#[serde(default)]
+	query: String,
+	loader: Option<String>,
+	category: Option<String>,
+	game_version: Option<String>,
+	#[serde(default)]
+	sort: PluginSort,
+	page: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct PluginVersionsQuery {
+	#[serde(default)]
+	source: PluginOrigin,
+	loader: Option<String>,
+	game_version: Option<String>,
+}
 
-	let servers: Vec<MinecraftServer> = serde_json::from_slice(&contents)
-		.map_err(|e| InternalError(format!("failed to parse servers.json: {e}")))?;
+#[derive(Deserialize)]
+struct PluginSourceQuery {
+	#[serde(default)]
+	source: PluginOrigin,
+}
+
+#[derive(Deserialize)]
+struct CommandQuery {
+	command: String,
+}
 
-	servers
-		.into_iter()
-		.find(|s| s.name() == name)
-		.ok_or_else(|| NotFound(format!("server '{}' not found", name)))
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PolicyMode {
+	Never,
+	OnFailure,
+	Always,
 }
 
-pub(crate) fn mc_route() -> Router {
+#[derive(Deserialize)]
+struct PolicyQuery {
+	mode: PolicyMode,
+	max_retries: Option<u32>,
+	backoff_secs: Option<u64>,
+}
+
+impl PolicyQuery {
+	fn policy(&self) -> RestartPolicy {
+		let backoff_secs: u64 = self.backoff_secs.unwrap_or(DEFAULT_BACKOFF_SECS);
+
+		match self.mode {
+			PolicyMode::Never => RestartPolicy::Never,
+			PolicyMode::OnFailure => RestartPolicy::OnFailure {
+				max_retries: self.max_retries.unwrap_or(DEFAULT_MAX_RETRIES),
+				backoff_secs,
+			},
+			PolicyMode::Always => RestartPolicy::Always { backoff_secs },
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct StopQuery {
+	timeout: Option<u64>,
+}
+
+impl StopQuery {
+	fn limit(&self) -> Duration {
+		self.timeout
+			.map(Duration::from_secs)
+			.unwrap_or(STOP_TIMEOUT)
+	}
+}
+
+#[derive(Serialize)]
+struct ServerListing {
+	#[serde(flatten)]
+	server: MinecraftServer,
+	ping: Option<ServerPing>,
+}
+
+#[derive(Clone, FromRef)]
+struct McState {
+	supervisor: Supervisor,
+	registry: ServerRegistry,
+	jobs: Jobs,
+	catalog: VersionCatalog,
+	cache: ArtifactCache,
+	/// New servers get their directory in here, upgrade snapshots go in `SNAPSHOT_DIR` below it.
+	server_root: PathBuf,
+}
+
+fn plugin_error(error: PluginError) -> RouteError {
+	match error {
+		PluginError::UnsupportedLoader(_) => BadRequest(error.to_string()),
+		PluginError::NoMatchingVersion(_) => NotFound(error.to_string()),
+		_ => {
+			error!("Plugin site error: {}", error);
+			InternalError(error.to_string())
+		}
+	}
+}
+
+fn registry_error(error: RegistryError) -> RouteError {
+	match error {
+		RegistryError::NotFound(name) => NotFound(format!("server '{}' not found", name)),
+		RegistryError::DuplicateName(_) => Conflict(error.to_string()),
+		_ => {
+			error!("Server registry error: {}", error);
+			InternalError(error.to_string())
+		}
+	}
+}
+
+pub(crate) fn mc_route(
+	registry: ServerRegistry,
+	cache: ArtifactCache,
+	server_root: impl Into<PathBuf>,
+) -> Router {
 	Router::new()
 		.route("/plugin/trending", get(trending_plugins))
+		.route("/plugin/search", get(search_plugins))
 		.route("/server/list", get(servers))
+		.route("/java", get(java_runtimes))
+		.route("/versions/{brand}", get(brand_versions))
+		.route("/versions/{brand}/{version}/builds", get(brand_builds))
+		.route("/jobs", get(list_jobs))
+		.route("/jobs/{id}", get(job_status))
 		.route("/server/create/{brand}/{version}", get(create_server))
 		.route(
 			"/server/create/{brand}/{version}/{name}",
 			get(create_server_with_name),
 		)
 		.route("/server/start/{server}", get(start_server))
+		.route("/server/{server}/status", get(server_status))
+		.route("/server/{server}/stop", get(stop_server))
+		.route("/server/{server}/kill", get(kill_server))
+		.route("/server/{server}/restart", get(restart_server))
+		.route("/server/{server}/crashes", get(server_crashes))
+		.route("/server/{server}/policy", get(set_restart_policy))
+		.route("/server/{server}/eula", get(set_eula))
+		.route(
+			"/server/{server}/launch",
+			get(get_launch_profile).post(set_launch_profile),
+		)
 		.route(
 			"/server/{server}/add/{plugin}/{version}",
 			get(add_plugin_to_server),
 		)
+		.route("/server/{server}/upgrade/{version}", get(upgrade_server))
 		.route("/server/{server}/delete", get(delete_server))
 		.route("/server/{server}/plugin/list", get(get_plugins_from_server))
 		.route("/server/{server}/logs", get(get_log))
+		.route("/server/{server}/console", get(server_console))
+		.route("/server/{server}/command", get(send_command))
 		.route("/plugin/versions/{plugin}", get(get_versions))
+		.with_state(McState {
+			supervisor: Supervisor::with_registry(registry.clone()),
+			registry,
+			jobs: Jobs::new(),
+			catalog: VersionCatalog::new(CATALOG_TTL),
+			cache,
+			server_root: server_root.into(),
+		})
 }
 
-const MAX_VERSIONS: i16 = 25;
-
 #[axum::debug_handler]
-async fn get_versions(Path(plugin): Path<String>) -> Result<Json<Vec<String>>, RouteError> {
-	let versions = fetch_plugin_versions(&Client::new(), &plugin, MAX_VERSIONS).await;
+async fn get_versions(
+	Path(plugin): Path<String>,
+	Query(query): Query<PluginVersionsQuery>,
+) -> Result<Json<Vec<PluginVersion>>, RouteError> {
+	let loaders: Vec<&str> = query.loader.as_deref().into_iter().collect();
+
+	let versions = query
+		.source
+		.versions(
+			&Client::new(),
+			&plugin,
+			&loaders,
+			query.game_version.as_deref(),
+		)
+		.await;
 
 	match versions {
 		Ok(v) => Ok(Json(v)),
@@ -95,6 +309,28 @@ async fn get_versions(Path(plugin): Path<String>) -> Result<Json<Vec<String>>, R
 	}
 }
 
+#[axum::debug_handler]
+async fn search_plugins(
+	Query(query): Query<PluginSearchQuery>,
+) -> Result<Json<SearchPage>, RouteError> {
+	let loaders: Vec<&str> = query.loader.as_deref().into_iter().collect();
+	let search = PluginSearch {
+		query: query.query,
+		category: query.category,
+		game_version: query.game_version,
+		sort: query.sort,
+		page: query.page.unwrap_or(1),
+	};
+
+	let page = query
+		.source
+		.search(&Client::new(), &search, &loaders)
+		.await
+		.map_err(plugin_error)?;
+
+	Ok(Json(page))
+}
+
 #[axum::debug_handler]
 async fn trending_plugins(
 	Query(query): Query<TrendingQuery>,
@@ -111,27 +347,36 @@ async fn trending_plugins(
 	}
 }
 
-#[axum::debug_handler]
-async fn servers() -> Result<Json<String>, (StatusCode, String)> {
-	let path: &std::path::Path = std::path::Path::new(CONF_LOCATION);
-	let mut file: File = File::open(path)
-		.await
-		.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-	let mut contents: Vec<u8> = vec![];
-	file.read_to_end(&mut contents).await.unwrap();
-
-	let cont: &str = from_utf8(&contents).unwrap();
-
-	Ok(Json(cont.parse().unwrap()))
+#[axum::debug_handler(state = McState)]
+async fn servers(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+) -> Result<Json<Vec<ServerListing>>, RouteError> {
+	let servers: Vec<MinecraftServer> = registry.load().await.map_err(registry_error)?;
+	let supervisor: &Supervisor = &supervisor;
+
+	let listings = join_all(servers.into_iter().map(|server| async move {
+		// stopped servers would only time out, or answer with another server on the same port.
+		let ping = if supervisor.status(server.name()).await.state.is_active() {
+			server.ping().await.ok()
+		} else {
+			None
+		};
+
+		ServerListing { server, ping }
+	}))
+	.await;
+
+	Ok(Json(listings))
 }
 
 #[axum::debug_handler]
 async fn get_log(
+	State(registry): State<ServerRegistry>,
 	Query(distance): Query<usize>,
 	Path(server_name): Path<String>,
 ) -> Result<String, RouteError> {
-	let mut server = load_server_by_name(&server_name).await?;
+	let mut server = registry.get(&server_name).await.map_err(registry_error)?;
 
 	if let Err(error) = server.refresh_log_cache().await {
 		error!("Error refreshing log cache: {}", error);
@@ -144,114 +389,529 @@ async fn get_log(
 	Ok(String::new())
 }
 
-#[axum::debug_handler]
-async fn start_server(Path(server_name): Path<String>) -> Result<String, RouteError> {
-	let server = load_server_by_name(&server_name).await?;
+#[axum::debug_handler(state = McState)]
+async fn server_console(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	Path(server_name): Path<String>,
+	upgrade: WebSocketUpgrade,
+) -> Result<Response, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+	let name: String = server.name().to_string();
+	let console: Console = supervisor.console(&name).await;
+
+	Ok(upgrade.on_upgrade(move |socket| attach_console(socket, supervisor, name, console)))
+}
 
-	match MinecraftServer::turn_on(&server).await {
-		Ok(_) => {
-			info!("Starting Minecraft server {}", server.name());
+/// Replays the scrollback, then forwards output to the client and client text to stdin.
+async fn attach_console(socket: WebSocket, supervisor: Supervisor, name: String, console: Console) {
+	let (mut sender, mut receiver) = socket.split();
+	let (history, mut lines) = console.subscribe();
 
-			Ok(String::from("Started server..."))
+	for line in history {
+		if sender.send(Message::Text(line.into())).await.is_err() {
+			return;
 		}
-		Err(error) => {
-			error!("Error trying to run Minecraft server.");
+	}
 
-			Ok(format!("Cause: {}", error))
+	loop {
+		tokio::select! {
+			line = lines.recv() => match line {
+				Ok(line) => {
+					if sender.send(Message::Text(line.into())).await.is_err() {
+						break;
+					}
+				}
+				Err(RecvError::Lagged(skipped)) => {
+					warn!("Console client for {} skipped {} lines", name, skipped);
+				}
+				Err(RecvError::Closed) => break,
+			},
+			message = receiver.next() => match message {
+				Some(Ok(Message::Text(command))) => {
+					if let Err(error) = supervisor.send_command(&name, command.as_str()).await {
+						warn!("Console command for {} rejected: {}", name, error);
+
+						if sender.send(Message::Text(error.to_string().into())).await.is_err() {
+							break;
+						}
+					}
+				}
+				Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+				Some(Ok(_)) => {}
+			},
 		}
 	}
+
+	debug!("Console client for {} disconnected", name);
+}
+
+#[axum::debug_handler]
+async fn send_command(
+	State(registry): State<ServerRegistry>,
+	Path(server_name): Path<String>,
+	Query(query): Query<CommandQuery>,
+) -> Result<String, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+	let keyring = KeyringService::new(SERVICE_NAME);
+
+	let mut rcon = server.rcon(&keyring).await.map_err(|error| {
+		error!("Cannot open RCON session for {}: {}", server.name(), error);
+
+		Conflict(format!(
+			"RCON unavailable for '{}': {}",
+			server.name(),
+			error
+		))
+	})?;
+
+	info!("Running '{}' on {}", query.command, server.name());
+
+	rcon.command(&query.command)
+		.await
+		.map_err(|error| InternalError(error.to_string()))
+}
+
+#[axum::debug_handler(state = McState)]
+async fn start_server(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	Path(server_name): Path<String>,
+) -> Result<String, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	supervisor.start(&server).await.map_err(supervisor_error)?;
+
+	info!("Starting Minecraft server {}", server.name());
+
+	Ok(String::from("Started server..."))
+}
+
+#[axum::debug_handler(state = McState)]
+async fn stop_server(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	Path(server_name): Path<String>,
+	Query(query): Query<StopQuery>,
+) -> Result<Json<ServerStatus>, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	info!("Stopping Minecraft server {}", server.name());
+
+	let status = server
+		.stop(&supervisor, query.limit())
+		.await
+		.map_err(supervisor_error)?;
+
+	Ok(Json(status))
+}
+
+#[axum::debug_handler(state = McState)]
+async fn kill_server(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	Path(server_name): Path<String>,
+) -> Result<Json<ServerStatus>, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	let status = server.kill(&supervisor).await.map_err(supervisor_error)?;
+
+	Ok(Json(status))
+}
+
+#[axum::debug_handler(state = McState)]
+async fn restart_server(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	Path(server_name): Path<String>,
+	Query(query): Query<StopQuery>,
+) -> Result<Json<ServerStatus>, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	info!("Restarting Minecraft server {}", server.name());
+
+	let status = server
+		.restart(&supervisor, query.limit())
+		.await
+		.map_err(supervisor_error)?;
+
+	Ok(Json(status))
+}
+
+fn eula_not_accepted(name: &str) -> RouteError {
+	EulaNotAccepted(format!(
+		"server '{}' cannot start until the EULA ({}) is accepted with accept_eula=true",
+		name, EULA_URL
+	))
+}
+
+fn supervisor_error(error: SupervisorError) -> RouteError {
+	error!("Supervisor error: {}", error);
+
+	match error {
+		SupervisorError::AlreadyRunning(_)
+		| SupervisorError::NotRunning(_)
+		| SupervisorError::Busy(_) => Conflict(error.to_string()),
+		SupervisorError::EulaNotAccepted(name) => eula_not_accepted(&name),
+		_ => InternalError(error.to_string()),
+	}
+}
+
+#[axum::debug_handler(state = McState)]
+async fn server_crashes(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	Path(server_name): Path<String>,
+) -> Result<Json<Vec<CrashRecord>>, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	Ok(Json(supervisor.crashes(server.name()).await))
+}
+
+/// Takes effect on the next start, the running process keeps the policy it was started with.
+#[axum::debug_handler]
+async fn set_restart_policy(
+	State(registry): State<ServerRegistry>,
+	Path(server_name): Path<String>,
+	Query(query): Query<PolicyQuery>,
+) -> Result<Json<RestartPolicy>, RouteError> {
+	let policy: RestartPolicy = query.policy();
+
+	registry
+		.update(&server_name, |server| {
+			server.with_restart_policy(policy.clone());
+		})
+		.await
+		.map_err(registry_error)?;
+
+	info!("Restart policy for {} is now {:?}", server_name, policy);
+
+	Ok(Json(policy))
+}
+
+#[axum::debug_handler]
+async fn set_eula(
+	State(registry): State<ServerRegistry>,
+	Path(server_name): Path<String>,
+	Query(eula): Query<EulaQuery>,
+) -> Result<String, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	server
+		.write_eula(eula.accept_eula)
+		.await
+		.map_err(|e| InternalError(format!("failed to write eula.txt: {e}")))?;
+
+	info!("Set EULA for {} to {}", server_name, eula.accept_eula);
+
+	Ok(format!("eula={}", eula.accept_eula))
+}
+
+#[axum::debug_handler]
+async fn brand_versions(
+	State(catalog): State<VersionCatalog>,
+	Path(brand): Path<ServerBrand>,
+) -> Result<Json<Vec<CatalogVersion>>, RouteError> {
+	catalog
+		.versions(&Client::new(), &brand)
+		.await
+		.map(Json)
+		.map_err(|e| InternalError(format!("cannot list {:?} versions: {e}", brand)))
+}
+
+#[axum::debug_handler]
+async fn brand_builds(
+	State(catalog): State<VersionCatalog>,
+	Path((brand, version)): Path<(ServerBrand, String)>,
+) -> Result<Json<VersionBuilds>, RouteError> {
+	catalog
+		.builds(&Client::new(), &brand, &version)
+		.await
+		.map(Json)
+		.map_err(|e| match e {
+			HttpClientError::VersionNotFound(_) => NotFound(e.to_string()),
+			_ => InternalError(format!("cannot list {:?} builds: {e}", brand)),
+		})
+}
+
+#[axum::debug_handler]
+async fn java_runtimes() -> Json<Vec<JavaRuntime>> {
+	Json(JavaRegistry::discover().await.runtimes().to_vec())
+}
+
+#[axum::debug_handler]
+async fn get_launch_profile(
+	State(registry): State<ServerRegistry>,
+	Path(server_name): Path<String>,
+) -> Result<Json<LaunchProfile>, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	Ok(Json(server.launch_profile().clone()))
 }
 
+/// Takes effect on the next start.
 #[axum::debug_handler]
+async fn set_launch_profile(
+	State(registry): State<ServerRegistry>,
+	Path(server_name): Path<String>,
+	Json(profile): Json<LaunchProfile>,
+) -> Result<Json<LaunchProfile>, RouteError> {
+	profile
+		.validate(&JavaRegistry::discover().await)
+		.map_err(BadRequest)?;
+
+	registry
+		.update(&server_name, |server| {
+			server.with_launch_profile(profile.clone());
+		})
+		.await
+		.map_err(registry_error)?;
+
+	info!("Updated launch profile for {}", server_name);
+
+	Ok(Json(profile))
+}
+
+#[axum::debug_handler(state = McState)]
+async fn server_status(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	Path(server_name): Path<String>,
+) -> Result<Json<ServerStatus>, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	Ok(Json(supervisor.status(server.name()).await))
+}
+
+#[axum::debug_handler(state = McState)]
 async fn add_plugin_to_server(
+	State(registry): State<ServerRegistry>,
+	State(cache): State<ArtifactCache>,
 	Path((server_name, plugin_name, plugin_version)): Path<(String, String, String)>,
-) -> Result<(), RouteError> {
-	let server = load_server_by_name(&server_name).await?;
+	Query(query): Query<PluginSourceQuery>,
+) -> Result<Json<PluginInstall>, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	if !server.brand().supports_plugins() {
+		warn!("Cannot add plugins to a {:?} server", server.brand());
 
-	if server.brand().eq(&Vanilla) {
-		warn!("Cannot add plugins to vanilla server");
+		return Err(InternalError(format!(
+			"Cannot download plugins on a {:?} server. Try using a paper server?",
+			server.brand()
+		)));
+	}
 
-		return Err(InternalError(String::from(
-			"Cannot download plugins on a vanilla server. Try using a paper server?",
+	if !query.source.serves(server.brand().plugin_loaders()) {
+		return Err(BadRequest(format!(
+			"{:?} has nothing for {:?} servers",
+			query.source,
+			server.brand()
 		)));
 	}
 
-	let plugin = Plugin::new(plugin_name.clone(), plugin_version);
+	if !is_plugin_file_name(&plugin_name) {
+		return Err(BadRequest(format!("invalid plugin name '{}'", plugin_name)));
+	}
+
+	let plugin = Plugin::new(plugin_name.clone(), plugin_version).with_source(query.source);
 
-	match MinecraftServer::add_plugin(&server, &plugin).await {
-		Ok(_) => {
-			info!("Downloading plugin {}", plugin.name())
+	let report: PluginInstall = match server.install_plugin(&cache, plugin).await {
+		Ok(report) => {
+			info!(
+				"Installed {} plugin(s) on {}",
+				report.installed.len(),
+				server_name
+			);
+			report
 		}
 
 		Err(error) => {
-			error!(
-				"Error trying to download plugin {}. {}",
-				plugin.name(),
-				error
-			);
+			error!("Error trying to download plugin {}. {}", plugin_name, error);
 
 			return Err(InternalError(String::from(
 				"Plugin doesn't exist or something else is wrong?",
 			)));
 		}
-	}
-	Ok(())
+	};
+
+	registry
+		.update(&server_name, |server| {
+			for plugin in &report.installed {
+				server.with_plugin(plugin.clone());
+			}
+		})
+		.await
+		.map_err(registry_error)?;
+
+	Ok(Json(report))
 }
 
 #[axum::debug_handler]
 async fn get_plugins_from_server(
+	State(registry): State<ServerRegistry>,
 	Path(server_name): Path<String>,
-) -> Result<Json<Option<Vec<Plugin>>>, RouteError> {
-	let server = load_server_by_name(&server_name).await?;
-
-	match server.plugins() {
-		None => Ok(Json(None)),
-
-		Some(vec) => {
-			let result = Some(vec);
-
-			Ok(Json(result.cloned()))
-		}
+) -> Result<Json<Vec<InstalledJar>>, RouteError> {
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+	let dir = format!("{}/{}", server.directory(), server.brand().plugin_dir());
+
+	let mut jars: Vec<InstalledJar> = scan(dir.as_ref()).await.map_err(|error| {
+		error!("Could not list plugins of {}. {}", server_name, error);
+		InternalError(error.to_string())
+	})?;
+
+	let recorded: &[Plugin] = server.plugins().map(Vec::as_slice).unwrap_or_default();
+
+	for jar in &mut jars {
+		jar.source = recorded
+			.iter()
+			.find(|plugin| plugin.name() == jar.stem())
+			.map(Plugin::source);
 	}
+
+	Ok(Json(jars))
 }
 
-#[axum::debug_handler]
+#[axum::debug_handler(state = McState)]
 async fn create_server_with_name(
+	State(state): State<McState>,
 	Path((brand, version, name)): Path<(ServerBrand, String, String)>,
-) -> Result<(), RouteError> {
-	create_server_inner(brand, version, Some(name)).await
+	Query(query): Query<CreateQuery>,
+) -> Result<(StatusCode, Json<JobStatus>), RouteError> {
+	create_server_inner(state, brand, version, Some(name), query).await
 }
 
-#[axum::debug_handler]
+#[axum::debug_handler(state = McState)]
 async fn create_server(
+	State(state): State<McState>,
 	Path((brand, version)): Path<(ServerBrand, String)>,
-) -> Result<(), RouteError> {
-	create_server_inner(brand, version, None).await
+	Query(query): Query<CreateQuery>,
+) -> Result<(StatusCode, Json<JobStatus>), RouteError> {
+	create_server_inner(state, brand, version, None, query).await
 }
 
+/// Checks the request up front, then downloads and registers the server as a job.
 async fn create_server_inner(
+	state: McState,
 	brand: ServerBrand,
 	version: String,
 	name: Option<String>,
-) -> Result<(), RouteError> {
+	query: CreateQuery,
+) -> Result<(StatusCode, Json<JobStatus>), RouteError> {
+	let McState {
+		registry,
+		jobs,
+		cache,
+		server_root,
+		..
+	} = state;
+
+	// a name given up front is checked before asking upstream about the version.
+	if let Some(name) = &name {
+		check_server_name(&registry, name).await?;
+	}
+
+	let version: String = brand
+		.resolve_version(&Client::new(), &version)
+		.await
+		.map_err(|e| InternalError(format!("cannot resolve version {}: {e}", version)))?;
+
+	let server_name: String = match name {
+		Some(name) => name,
+		None => {
+			let name: String = format!("{:?}-{}", brand, version).to_lowercase();
+			check_server_name(&registry, &name).await?;
+			name
+		}
+	};
+
+	let accept_eula: bool = query.accept_eula;
 	let mut new_server = MinecraftServer::new();
 
 	new_server
 		.with_brand(brand)
-		.with_version(BuildInfo { version })
-		.build();
+		.with_version(query.build_info(version))
+		.with_name(Some(server_name.clone()))
+		.with_directory(Some(
+			server_root
+				.join(&server_name)
+				.to_string_lossy()
+				.into_owned(),
+		));
+
+	create_dir_all(&server_root)
+		.await
+		.map_err(|e| InternalError(format!("failed to create server directory: {e}")))?;
+
+	// creating the directory itself reserves the name against a concurrent create.
+	create_dir(new_server.directory())
+		.await
+		.map_err(|e| match e.kind() {
+			ErrorKind::AlreadyExists => {
+				Conflict(format!("directory for '{}' already exists", server_name))
+			}
+			_ => InternalError(format!("failed to create server directory: {e}")),
+		})?;
+
+	info!("Creating new server {}...", server_name);
 
-	let path_fmt_str: &String = &format!("/server/{}", name.clone().unwrap());
-	let path: &str = path_fmt_str.as_str();
+	let job = jobs
+		.spawn(format!("Create server {}", server_name), move |progress| {
+			install_server(registry, cache, new_server, accept_eula, progress)
+		})
+		.await;
 
-	if let Err(e) = create_dir_all(path).await {
-		error!("Error creating directory: {}", e)
+	Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn check_server_name(registry: &ServerRegistry, name: &str) -> Result<(), RouteError> {
+	if !is_valid_server_name(name) {
+		return Err(BadRequest(format!("invalid server name '{}'", name)));
+	}
+
+	if registry.contains(name).await.map_err(registry_error)? {
+		return Err(Conflict(format!(
+			"a server named '{}' already exists",
+			name
+		)));
 	}
 
-	let jar_name: String = match new_server.try_download(path).await {
-		Ok(file_name) => {
+	Ok(())
+}
+
+/// Frees the reserved directory again when any step fails, so the name can be retried.
+async fn install_server(
+	registry: ServerRegistry,
+	cache: ArtifactCache,
+	new_server: MinecraftServer,
+	accept_eula: bool,
+	progress: watch::Sender<DownloadProgress>,
+) -> Result<(), String> {
+	let path: String = new_server.directory();
+	let result = install_server_files(registry, &cache, new_server, accept_eula, progress).await;
+
+	if result.is_err()
+		&& let Err(e) = remove_dir_all(&path).await
+	{
+		warn!("Could not clean up {}: {}", path, e);
+	}
+
+	result
+}
+
+async fn install_server_files(
+	registry: ServerRegistry,
+	cache: &ArtifactCache,
+	mut new_server: MinecraftServer,
+	accept_eula: bool,
+	progress: watch::Sender<DownloadProgress>,
+) -> Result<(), String> {
+	let path: String = new_server.directory();
+	let server_name: String = new_server.name().to_string();
+
+	let installed: Installed = match new_server.try_download(cache, &path, Some(&progress)).await {
+		Ok(installed) => {
 			info!("Downloaded jar!");
-			file_name
+			installed
 		}
 
 		Err(error) => {
@@ -260,41 +920,196 @@ async fn create_server_inner(
 
 			debug!("{}", error);
 
-			return Err(InternalError(String::from(
-				"Cannot download version. Did you type it correctly?",
-			)));
+			return Err(format!(
+				"Cannot download version. Did you type it correctly? {}",
+				error
+			));
 		}
 	};
 
-	let server_name = name.unwrap_or(jar_name);
-	new_server.with_name(Some(server_name.clone()));
+	new_server.with_build_id(installed.build);
 
-	info!("Creating new server {}...", server_name);
+	match installed.launch {
+		// turn_on launches `<name>.jar` unless told otherwise.
+		LaunchTarget::Jar(jar_name) => {
+			rename(
+				format!("{}/{}", path, jar_name),
+				format!("{}/{}.jar", path, server_name),
+			)
+			.await
+			.map_err(|e| format!("failed to rename server jar: {e}"))?;
+		}
+		target => {
+			new_server.with_launch_target(Some(target));
+		}
+	}
+
+	match new_server.resolve_java_major(&Client::new()).await {
+		Ok(major) => {
+			new_server.with_java_major(major);
+		}
+		Err(error) => warn!("Could not look up the required Java version: {}", error),
+	}
 
-	let path = std::path::Path::new(CONF_LOCATION);
-	let mut file = File::open(path)
+	new_server
+		.write_eula(accept_eula)
 		.await
-		.map_err(|e| InternalError(format!("failed to create servers.json: {e}")))?;
+		.map_err(|e| format!("failed to write eula.txt: {e}"))?;
 
-	let json_str = serde_json::to_string(&new_server)
-		.map_err(|_| InternalError(String::from("serialization failed")))?;
+	if !accept_eula {
+		warn!(
+			"EULA not accepted for {}, it will not start until it is",
+			server_name
+		);
+	}
 
-	file.write_all(json_str.as_bytes())
+	registry
+		.insert(new_server)
 		.await
-		.map_err(|e| InternalError(format!("failed to write file: {e}")))?;
+		.map_err(|e| e.to_string())?;
 
+	info!("Created server {}", server_name);
 	Ok(())
 }
 
+/// Swaps the server onto another version or build as a job, rolling back if it can't boot.
+#[axum::debug_handler(state = McState)]
+async fn upgrade_server(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	State(jobs): State<Jobs>,
+	State(cache): State<ArtifactCache>,
+	State(server_root): State<PathBuf>,
+	Path((server_name, version)): Path<(String, String)>,
+	Query(query): Query<UpgradeQuery>,
+) -> Result<(StatusCode, Json<JobStatus>), RouteError> {
+	// held until the job ends, one upgrade at a time and no starts meanwhile. Taken before
+	// the read so the job never works from a record that changed in between.
+	let lock: ServerLock = supervisor
+		.lock(&server_name)
+		.await
+		.map_err(supervisor_error)?;
+
+	let server = registry.get(&server_name).await.map_err(registry_error)?;
+
+	let version: String = server
+		.brand()
+		.resolve_version(&Client::new(), &version)
+		.await
+		.map_err(|e| InternalError(format!("cannot resolve version {}: {e}", version)))?;
+
+	let target: BuildInfo = query.build_info(version);
+
+	info!(
+		"Upgrading {} from {} to {}",
+		server_name,
+		server.version(),
+		target.version
+	);
+
+	let job = jobs
+		.spawn(
+			format!("Upgrade server {} to {}", server_name, target.version),
+			move |progress| async move {
+				let upgraded: MinecraftServer = server
+					.upgrade(
+						&supervisor,
+						&lock,
+						&cache,
+						&server_root.join(SNAPSHOT_DIR),
+						target,
+						Some(&progress),
+					)
+					.await
+					.map_err(|e| e.to_string())?;
+
+				// the record may have changed while the job ran, only touch what it replaced.
+				registry
+					.update(&server_name, |s| {
+						s.with_version(upgraded.build_info().clone())
+							.with_launch_target(upgraded.recorded_launch_target().cloned());
+
+						if let Some(major) = upgraded.recorded_java_major() {
+							s.with_java_major(major);
+						}
+					})
+					.await
+					.map_err(|e| e.to_string())?;
+
+				Ok(())
+			},
+		)
+		.await;
+
+	Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+#[axum::debug_handler]
+async fn list_jobs(State(jobs): State<Jobs>) -> Json<Vec<JobStatus>> {
+	Json(jobs.list().await)
+}
+
 #[axum::debug_handler]
-async fn delete_server(Path(server_name): Path<String>) -> Result<(), RouteError> {
-	let server = load_server_by_name(&server_name).await?;
+async fn job_status(
+	State(jobs): State<Jobs>,
+	Path(id): Path<Uuid>,
+) -> Result<Json<JobStatus>, RouteError> {
+	jobs.status(id)
+		.await
+		.map(Json)
+		.ok_or_else(|| NotFound(format!("job {} not found", id)))
+}
+
+// names become directory names, so keep them to something path safe.
+fn is_valid_server_name(name: &str) -> bool {
+	!name.is_empty()
+		&& name.len() <= 64
+		&& !name.starts_with('.')
+		&& name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[axum::debug_handler(state = McState)]
+async fn delete_server(
+	State(registry): State<ServerRegistry>,
+	State(supervisor): State<Supervisor>,
+	Path(server_name): Path<String>,
+) -> Result<(), RouteError> {
+	// an upgrade holds the lock while the server is stopped.
+	let _lock: ServerLock = match supervisor.lock(&server_name).await {
+		Ok(lock) => lock,
+		Err(SupervisorError::AlreadyRunning(_)) => {
+			return Err(Conflict(format!(
+				"server '{}' is still running, stop it first",
+				server_name
+			)));
+		}
+		Err(error) => return Err(supervisor_error(error)),
+	};
+
+	let server = registry
+		.remove(&server_name)
+		.await
+		.map_err(registry_error)?;
 	let result = server.delete().await;
 
 	if let Err(error) = result {
 		error!("Error deleting server: {}", error)
 	}
 
+	let keyring = KeyringService::new(SERVICE_NAME);
+	let key: String = server.rcon_key();
+
+	if keyring.secret_exists(&key).await
+		&& let Err(error) = keyring.delete_secret(&key).await
+	{
+		error!(
+			"Error deleting the RCON password of {}: {}",
+			server_name, error
+		)
+	}
+
 	Ok(())
 }
 
@@ -303,9 +1118,51 @@ mod tests {
 	use super::*;
 	use axum::body::Body;
 	use axum::http::{Request, StatusCode};
+	use axum::response::IntoResponse;
 	use axum::response::Response;
+	use std::sync::atomic::{AtomicUsize, Ordering};
 	use tower::ServiceExt;
 
+	static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+	fn temp_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!(
+			"lunara-mc-route-{}-{}-{}",
+			std::process::id(),
+			NEXT.fetch_add(1, Ordering::Relaxed),
+			name
+		))
+	}
+
+	fn test_cache() -> ArtifactCache {
+		ArtifactCache::new(temp_path("cache"), 1 << 30)
+	}
+
+	fn mc_route() -> Router {
+		super::mc_route(
+			ServerRegistry::new(crate::mc::registry::REGISTRY_LOCATION),
+			test_cache(),
+			temp_path("servers"),
+		)
+	}
+
+	/// Router backed by a throwaway registry that knows about "testserver".
+	async fn test_route() -> Router {
+		let registry = ServerRegistry::new(temp_path("registry.json"));
+
+		let mut server = MinecraftServer::new();
+		server.with_name(Some(String::from("testserver")));
+		registry.insert(server).await.unwrap();
+
+		let mut modded = MinecraftServer::new();
+		modded
+			.with_name(Some(String::from("modded")))
+			.with_brand(ServerBrand::Fabric);
+		registry.insert(modded).await.unwrap();
+
+		super::mc_route(registry, test_cache(), temp_path("servers"))
+	}
+
 	#[tokio::test]
 	async fn mc_route_has_trending_plugins_route() {
 		let app = mc_route();
@@ -342,7 +1199,7 @@ mod tests {
 
 	#[tokio::test]
 	async fn mc_route_has_create_server_route() {
-		let app = mc_route();
+		let app = test_route().await;
 
 		let response: Response = app
 			.oneshot(
@@ -374,6 +1231,414 @@ mod tests {
 		assert_eq!(response.status(), StatusCode::NOT_FOUND);
 	}
 
+	#[tokio::test]
+	async fn mc_route_has_server_status_route() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/server/testserver/status")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_ne!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[tokio::test]
+	async fn mc_route_has_lifecycle_routes() {
+		for action in ["stop", "kill", "restart"] {
+			let app = test_route().await;
+
+			let response: Response = app
+				.oneshot(
+					Request::builder()
+						.uri(format!("/server/testserver/{}", action))
+						.body(Body::empty())
+						.unwrap(),
+				)
+				.await
+				.unwrap();
+
+			assert_ne!(response.status(), StatusCode::NOT_FOUND);
+		}
+	}
+
+	#[tokio::test]
+	async fn mc_route_has_command_route() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/server/testserver/command?command=list")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_ne!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[tokio::test]
+	async fn mc_route_console_requires_websocket_upgrade() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/server/testserver/console")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_ne!(response.status(), StatusCode::NOT_FOUND);
+		assert!(response.status().is_client_error());
+	}
+
+	#[tokio::test]
+	async fn java_runtimes_route_lists_installs() {
+		let app = mc_route();
+
+		let response: Response = app
+			.oneshot(Request::builder().uri("/java").body(Body::empty()).unwrap())
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::OK);
+	}
+
+	#[tokio::test]
+	async fn versions_route_rejects_unknown_brand() {
+		let app = mc_route();
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/versions/Spigot")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
+	#[tokio::test]
+	async fn jobs_route_lists_and_reports_unknown_jobs() {
+		let app = mc_route();
+
+		let response: Response = app
+			.clone()
+			.oneshot(Request::builder().uri("/jobs").body(Body::empty()).unwrap())
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri(format!("/jobs/{}", Uuid::new_v4()))
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[tokio::test]
+	async fn set_launch_profile_rejects_invalid_heap() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.method("POST")
+					.uri("/server/testserver/launch")
+					.header("content-type", "application/json")
+					.body(Body::from(r#"{"max_heap":"lots"}"#))
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
+	#[tokio::test]
+	async fn set_launch_profile_rejects_arbitrary_java_path() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.method("POST")
+					.uri("/server/testserver/launch")
+					.header("content-type", "application/json")
+					.body(Body::from(
+						r#"{"java_path":"/bin/sh","jvm_args":["-c","id"]}"#,
+					))
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
+	#[tokio::test]
+	async fn start_is_refused_while_the_server_is_locked() {
+		let registry = ServerRegistry::new(temp_path("registry.json"));
+		let mut server = MinecraftServer::new();
+		server.with_name(Some(String::from("testserver")));
+		registry.insert(server).await.unwrap();
+
+		let supervisor = Supervisor::new();
+		let _lock: ServerLock = supervisor.lock("testserver").await.unwrap();
+
+		let error = start_server(
+			State(registry),
+			State(supervisor),
+			Path(String::from("testserver")),
+		)
+		.await
+		.unwrap_err();
+
+		assert_eq!(error.into_response().status(), StatusCode::CONFLICT);
+	}
+
+	#[tokio::test]
+	async fn set_launch_profile_rejects_code_running_jvm_args() {
+		for body in [
+			r#"{"jvm_args":["-XX:OnOutOfMemoryError=sh -c id"]}"#,
+			r#"{"jvm_args":["-javaagent:/tmp/agent.jar"]}"#,
+			r#"{"max_heap":"1G","jvm_args":["-Xmx64G"]}"#,
+		] {
+			let app = test_route().await;
+
+			let response: Response = app
+				.oneshot(
+					Request::builder()
+						.method("POST")
+						.uri("/server/testserver/launch")
+						.header("content-type", "application/json")
+						.body(Body::from(body))
+						.unwrap(),
+				)
+				.await
+				.unwrap();
+
+			assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+		}
+	}
+
+	#[tokio::test]
+	async fn unknown_server_is_not_found() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/server/missing/eula?accept_eula=true")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[tokio::test]
+	async fn plugin_list_is_empty_before_any_jar_is_installed() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/server/modded/plugin/list")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		assert_eq!(&body[..], b"[]");
+	}
+
+	#[tokio::test]
+	async fn stopped_servers_are_listed_without_a_ping() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/server/list")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		let listings: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+		assert_eq!(listings.as_array().unwrap().len(), 2);
+		assert!(listings[0]["ping"].is_null());
+		assert!(listings[1]["ping"].is_null());
+	}
+
+	#[tokio::test]
+	async fn plugin_search_rejects_unknown_sort() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/plugin/search?query=perms&sort=alphabetical")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
+	#[tokio::test]
+	async fn hangar_plugins_are_rejected_on_fabric() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/server/modded/add/LuckPerms/5.4?source=hangar")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+	}
+
+	#[tokio::test]
+	async fn upgrading_unknown_server_is_not_found() {
+		let app = test_route().await;
+
+		let response: Response = app
+			.oneshot(
+				Request::builder()
+					.uri("/server/missing/upgrade/1.21.1?channel=experimental")
+					.body(Body::empty())
+					.unwrap(),
+			)
+			.await
+			.unwrap();
+
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[tokio::test]
+	async fn create_rejects_taken_and_unsafe_names() {
+		for (uri, status) in [
+			(
+				"/server/create/Paper/1.21.1/testserver",
+				StatusCode::CONFLICT,
+			),
+			("/server/create/Paper/1.21.1/..", StatusCode::BAD_REQUEST),
+			// checked before `latest` is looked up upstream.
+			(
+				"/server/create/Vanilla/latest/testserver",
+				StatusCode::CONFLICT,
+			),
+			("/server/create/Vanilla/latest/..", StatusCode::BAD_REQUEST),
+		] {
+			let app = test_route().await;
+
+			let response: Response = app
+				.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+				.await
+				.unwrap();
+
+			assert_eq!(response.status(), status);
+		}
+	}
+
+	#[test]
+	fn server_names_must_be_path_safe() {
+		assert!(is_valid_server_name("paper-1.21.1"));
+		assert!(!is_valid_server_name(""));
+		assert!(!is_valid_server_name("../etc"));
+		assert!(!is_valid_server_name(".hidden"));
+	}
+
+	#[test]
+	fn missing_eula_is_a_failed_precondition() {
+		let response = supervisor_error(SupervisorError::EulaNotAccepted(String::from("test")))
+			.into_response();
+
+		assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+	}
+
+	#[test]
+	fn policy_query_fills_defaults() {
+		let query = PolicyQuery {
+			mode: PolicyMode::OnFailure,
+			max_retries: None,
+			backoff_secs: Some(2),
+		};
+
+		assert_eq!(
+			query.policy(),
+			RestartPolicy::OnFailure {
+				max_retries: DEFAULT_MAX_RETRIES,
+				backoff_secs: 2,
+			}
+		);
+	}
+
+	#[test]
+	fn create_query_carries_build_and_channel() {
+		let query = CreateQuery {
+			accept_eula: true,
+			build: Some(String::from("132")),
+			channel: ReleaseChannel::Experimental,
+		};
+
+		let info: BuildInfo = query.build_info(String::from("1.21.1"));
+
+		assert_eq!(info.version, "1.21.1");
+		assert_eq!(info.build.as_deref(), Some("132"));
+		assert_eq!(info.channel, ReleaseChannel::Experimental);
+	}
+
+	#[test]
+	fn stop_query_defaults_timeout() {
+		let query = StopQuery { timeout: None };
+
+		assert_eq!(query.limit(), STOP_TIMEOUT);
+	}
+
 	#[tokio::test]
 	async fn trending_plugins_accepts_query_param() {
 		let app = mc_route();
@@ -14,7 +14,9 @@ See the License for the specific language governing permissions and
 limitations under the License.
 
 */
-use crate::route::route_error::RouteError::{InternalError, NotFound};
+use crate::route::route_error::RouteError::{
+	BadRequest, Conflict, EulaNotAccepted, InternalError, NotFound,
+};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use thiserror::Error;
@@ -25,6 +27,12 @@ pub enum RouteError {
 	InternalError(String),
 	#[error("Not found: {0}")]
 	NotFound(String),
+	#[error("Conflict: {0}")]
+	Conflict(String),
+	#[error("Bad request: {0}")]
+	BadRequest(String),
+	#[error("EULA not accepted: {0}")]
+	EulaNotAccepted(String),
 }
 impl IntoResponse for RouteError {
 	fn into_response(self) -> Response {
@@ -37,6 +45,17 @@ impl IntoResponse for RouteError {
 			NotFound(error) => {
 				(StatusCode::NOT_FOUND, format!("Not found: {}", error)).into_response()
 			}
+			Conflict(error) => {
+				(StatusCode::CONFLICT, format!("Conflict: {}", error)).into_response()
+			}
+			BadRequest(error) => {
+				(StatusCode::BAD_REQUEST, format!("Bad request: {}", error)).into_response()
+			}
+			EulaNotAccepted(error) => (
+				StatusCode::PRECONDITION_FAILED,
+				format!("EULA not accepted: {}", error),
+			)
+				.into_response(),
 		}
 	}
 }
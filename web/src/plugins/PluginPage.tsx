@@ -18,7 +18,7 @@
 
 
 import Paper from '@mui/material/Paper';
-import {FormControl, IconButton, InputLabel, MenuItem, Select, Slider, Typography} from "@mui/material";
+import {FormControl, IconButton, InputLabel, MenuItem, Select, Slider, TextField, Typography} from "@mui/material";
 import {Close, InsertDriveFile} from "@mui/icons-material";
 import {ThemeToggle} from "../components/theme/ThemeToggle.tsx";
 import {useCallback, useEffect, useRef, useState} from "react";
@@ -53,13 +53,18 @@ function CachedImage({src, alt}: { src: string; alt: string }) {
 function PluginPage() {
     const [plugins, setPlugins] = useState<Plugin[]>([]);
     const [page, setPage] = useState<number>(1);
+    const [pageCount, setPageCount] = useState<number>(1);
+    const [query, setQuery] = useState<string>("");
+    const [sort, setSort] = useState<PluginSort>("stars");
     const [loading, setLoading] = useState(false);
     const [selectedPlugin, setSelectedPlugin] = useState<Plugin | null>(null);
     const [versions, setVersions] = useState<string[]>([]);
     const [loadingVersions, setLoadingVersions] = useState(false);
     const [servers, setServers] = useState<string[]>([]);
     const [selectedServer, setSelectedServer] = useState<string>("");
-    const debounceRef = useRef<ReturnType<typeof setTimeout> | null>(null);
+    const queryDebounceRef = useRef<ReturnType<typeof setTimeout> | null>(null);
+    const pageDebounceRef = useRef<ReturnType<typeof setTimeout> | null>(null);
+    const latestRequestRef = useRef<number>(0);
 
     useEffect(() => {
         fetch("http://localhost:5000/mc/server/list")
@@ -73,25 +78,45 @@ function PluginPage() {
             .catch(() => setServers([]));
     }, []);
 
-    const fetchPlugins = useCallback(async (pageNum: number) => {
+    const fetchPlugins = useCallback(async (pageNum: number, search: string, order: PluginSort) => {
+        // a slower, older search must not overwrite the results of a newer one.
+        const request = ++latestRequestRef.current;
+
         setLoading(true);
-        const result = await get_plugins(pageNum);
-        setPlugins(result);
+        const result = await get_plugins(pageNum, search, order);
+
+        if (request !== latestRequestRef.current) {
+            return;
+        }
+
+        setPlugins(result.plugins);
+        setPageCount(Math.max(1, Math.ceil(result.total / result.page_size)));
         setLoading(false);
     }, []);
 
     useEffect(() => {
-        fetchPlugins(page);
-    }, [page, fetchPlugins]);
+        fetchPlugins(page, query, sort);
+    }, [page, query, sort, fetchPlugins]);
+
+    const handleQueryChange = (value: string) => {
+        if (queryDebounceRef.current) {
+            clearTimeout(queryDebounceRef.current);
+        }
+
+        queryDebounceRef.current = setTimeout(() => {
+            setPage(1);
+            setQuery(value);
+        }, 300);
+    };
 
     const handleSliderChange = (_: unknown, value: number | number[]) => {
         const newPage = value as number;
 
-        if (debounceRef.current) {
-            clearTimeout(debounceRef.current);
+        if (pageDebounceRef.current) {
+            clearTimeout(pageDebounceRef.current);
         }
 
-        debounceRef.current = setTimeout(() => {
+        pageDebounceRef.current = setTimeout(() => {
             setPage(newPage);
         }, 300);
     };
@@ -102,7 +127,8 @@ function PluginPage() {
 
         const res = await fetch(`http://localhost:5000/mc/plugin/versions/${plugin.slug}`);
         if (res.ok) {
-            setVersions(await res.json());
+            const found: { name: string }[] = await res.json();
+            setVersions(found.map(version => version.name));
         } else {
             setVersions([]);
         }
@@ -126,6 +152,32 @@ function PluginPage() {
                 <ThemeToggle/>
             </header>
 
+            <Paper className="plugin-search" elevation={2}>
+                <TextField
+                    size="small"
+                    label="Search"
+                    fullWidth
+                    onChange={(e) => handleQueryChange(e.target.value)}
+                />
+                <FormControl size="small">
+                    <InputLabel>Sort</InputLabel>
+                    <Select
+                        label="Sort"
+                        value={sort}
+                        onChange={(e) => {
+                            setPage(1);
+                            setSort(e.target.value as PluginSort);
+                        }}
+                    >
+                        <MenuItem value="stars">Stars</MenuItem>
+                        <MenuItem value="downloads">Downloads</MenuItem>
+                        <MenuItem value="newest">Newest</MenuItem>
+                        <MenuItem value="updated">Recently updated</MenuItem>
+                        <MenuItem value="relevance">Relevance</MenuItem>
+                    </Select>
+                </FormControl>
+            </Paper>
+
             <div className="plugin-grid">
                 {loading && plugins.length === 0 && (
                     <Typography className="plugin-status">Loading...</Typography>
@@ -224,12 +276,13 @@ function PluginPage() {
 
             <Paper className="page-slider-container" elevation={2}>
                 <Typography variant="body2" fontWeight={500}>
-                    Page {page}
+                    Page {page} of {pageCount}
                 </Typography>
                 <Slider
+                    key={`${query}-${sort}`}
                     defaultValue={1}
                     min={1}
-                    max={20}
+                    max={pageCount}
                     step={1}
                     valueLabelDisplay="auto"
                     onChange={handleSliderChange}
@@ -239,40 +292,51 @@ function PluginPage() {
     );
 }
 
-async function get_plugins(page_number: number): Promise<Plugin[]> {
-    const response: Response = await fetch("http://localhost:5000/mc/plugin/trending?trending=" + page_number);
+async function get_plugins(page_number: number, query: string, sort: PluginSort): Promise<PluginResults> {
+    const params = new URLSearchParams({page: String(page_number), query, sort});
+    const response: Response = await fetch("http://localhost:5000/mc/plugin/search?" + params);
 
     if (!response.ok) {
-        return [];
+        return {plugins: [], total: 0, page_size: 1};
     }
 
-    const jsonData: TrendingPlugin[] = await response.json();
-
-    return jsonData.map((plugin) => ({
-        icon: plugin.avatarUrl,
-        name: plugin.name,
-        downloads: plugin.stats.downloads,
-        stars: plugin.stats.stars,
-        owner: plugin.namespace.owner,
-        slug: plugin.namespace.slug,
-        download_url: plugin.download_url,
-        version: plugin.version,
-    }));
+    const jsonData: SearchPage = await response.json();
+
+    return {
+        plugins: jsonData.results.map((project) => ({
+            icon: project.icon_url ?? "",
+            name: project.name,
+            downloads: project.downloads,
+            stars: project.stars,
+            owner: project.author,
+            slug: project.slug,
+        })),
+        total: jsonData.total,
+        page_size: jsonData.page_size,
+    };
 }
 
-interface TrendingPlugin {
-    name: string;
-    namespace: {
-        owner: string;
+type PluginSort = "stars" | "downloads" | "newest" | "updated" | "relevance";
+
+interface SearchPage {
+    total: number;
+    page: number;
+    page_size: number;
+    results: {
         slug: string;
-    };
-    stats: {
+        name: string;
+        author: string;
+        description: string;
         downloads: number;
         stars: number;
-    };
-    avatarUrl: string;
-    download_url: string;
-    version: string;
+        icon_url: string | null;
+    }[];
+}
+
+interface PluginResults {
+    plugins: Plugin[];
+    total: number;
+    page_size: number;
 }
 
 interface Plugin {
@@ -282,8 +346,6 @@ interface Plugin {
     stars: number;
     owner: string;
     slug: string;
-    download_url: string;
-    version: string;
 }
 
 export default PluginPage;
\ No newline at end of file